@@ -1,11 +1,17 @@
 use std::path;
 use std::fs;
 use std::env;
+use std::io;
 use regex::Regex;
 
+mod parser;
+mod scanner;
+use parser::{alt, any_char, many, map, parse_all, seq, tag, take_while, try_map, Input, ParseResult};
+
 fn main() {
     original_answer();
     silly_fun_parser_answer();
+    streamed_answer();
 }
 
 fn original_answer() {
@@ -44,13 +50,9 @@ fn original_answer() {
     println!("{:?}", answer);
 }
 
-fn parse_mul(str: &str) -> (i32, i32) {
-    let halves: Vec<_> = str.split(',').collect();
-    let first = halves[0].split('(').collect::<Vec<_>>()[1];
-    let second = halves[1].split(')').collect::<Vec<_>>()[0];
-    let f: i32 = first.parse().unwrap();
-    let s: i32 = second.parse().unwrap();
-    (f, s)
+fn parse_mul(str: &str) -> (i64, i64) {
+    let mul = map(seq((tag("mul("), number(), tag(","), number(), tag(")"))), |(_, f, _, s, _)| (f, s));
+    parse_all(mul, str).unwrap()
 }
 
 #[derive(Debug)]
@@ -101,207 +103,102 @@ fn silly_fun_parser_answer() {
         }
         Ok(data) => data
     };
-    let all_tokens: &[SingleToken] = &data.chars()
-        .map(|c| SingleToken::new(c))
-        .collect::<Vec<SingleToken>>();
-    let mut tokens = vec![];
-    let mut tokens_cursor = all_tokens.iter();
-    loop {
-        if tokens_cursor.len() == 0 { 
-            break;
-        }
-        let token = parse_dont(tokens_cursor.as_slice())
-            .or(parse_do(tokens_cursor.as_slice()))
-            .or(parse_m(tokens_cursor.as_slice()));
-        match token {
-            Some(token) => {
-                let advance_by = token.consumed();
-                tokens.push(token);
-                for _ in 0..advance_by {
-                    tokens_cursor.next();
-                }
-            }
-            None => {
-                tokens_cursor.next();
-            },
-        }
-    }
+    println!("{:?}", enabled_total(&data));
+}
+
+/* The corrupted memory is instructions with junk in between, so at every spot
+ * either an instruction parses or we throw one char away and try again.
+ */
+fn enabled_total(data: &str) -> i64 {
+    let scan = many(alt((map(instruction(), Some), map(any_char, |_| None))));
+    let (commands, _) = scan(Input::new(data)).unwrap();
 
     let mut answer = 0;
     let mut enabled = true;
-    for token in tokens {
-        match token {
+    for command in commands.into_iter().flatten() {
+        match command {
             Command::Do => enabled = true,
             Command::Dont => enabled = false,
-            Command::Mul(fst, snd, _) => {
-                if !enabled {
-                    continue;
-                }
-                answer += fst * snd;
-            }
+            Command::Mul(fst, snd) if enabled => answer += fst * snd,
+            Command::Mul(_, _) => {}
         }
     }
-    println!("{:?}", answer);    
-}
-
-fn is_digit_radix10(c: char) -> bool {
-    c.is_digit(10)
-}
-
-fn parse_do(tokens: &[SingleToken]) -> Option<Command> {
-    CompoundToken::matches(tokens, "do()").lift().map(|_| {
-        Command::Do
-    })
-}
-
-fn parse_dont(tokens: &[SingleToken]) -> Option<Command> {
-    CompoundToken::matches(tokens, "don't()").lift().map(|_| {
-        Command::Dont
-    })
+    answer
 }
 
-fn parse_m(tokens: &[SingleToken]) -> Option<Command> {
-    CompoundToken::matches(tokens, "mul(").lift().and_then(|_| {
-        let tokens = &tokens[4..];
-        CompoundToken::matches_func_while(tokens, is_digit_radix10).lift().and_then(|digits| {
-            let characters = digits.len();
-            if characters <= 0 || characters > 4 {
-                return None;
-            }
-            let first_digits: i32 = digits.iter().collect::<String>().parse().unwrap();
-            let tokens = &tokens[characters..];
-            let mut tokens_used: i32 = 4;
-            CompoundToken::matches(tokens, ",").lift().and_then(|_| {
-                let tokens = &tokens[1..];
-                tokens_used += 1;
-                CompoundToken::matches_func_while(tokens, is_digit_radix10).lift().and_then(|digits| {
-                    let characters = digits.len();
-                    if characters <= 0 || characters > 4 {
-                        return None;
-                    }
-                    for _ in digits.iter() {
-                        tokens_used += 1;
-                    }
-                    let second_digit: i32 = digits.iter().collect::<String>().parse().unwrap();
-                    let skip = characters as usize;
-                    let tokens = &tokens[skip..];
-                    CompoundToken::matches(tokens, ")").lift().and_then(|_| {
-                        tokens_used += 1;
-                        Some(Command::Mul(first_digits, second_digit, tokens_used))
-                    })
-                })
-            })
-        })
-    })
-}
-
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum Command {
-    Mul(i32, i32, i32),
+    Mul(i64, i64),
     Do,
     Dont,
 }
 
-impl Command {
-    fn consumed(&self) -> i32 {
-        match self {
-            Command::Do => 4,
-            Command::Dont => 7,
-            Command::Mul(_,_, tokens_used) => *tokens_used,
-        }
-    }
+fn number<'a>() -> impl Fn(Input<'a>) -> ParseResult<'a, i64> {
+    try_map(take_while("a digit", 1, 3, |c| c.is_ascii_digit()), |digits: &str| {
+        digits.parse().map_err(|_| format!("{} to be a number", digits))
+    })
 }
 
-#[derive(Debug)]
-enum CompoundToken {
-    Null,
-    Token(Vec<char>)
+fn instruction<'a>() -> impl Fn(Input<'a>) -> ParseResult<'a, Command> {
+    alt((
+        map(tag("do()"), |_| Command::Do),
+        map(tag("don't()"), |_| Command::Dont),
+        map(seq((tag("mul("), number(), tag(","), number(), tag(")"))), |(_, f, _, s, _)| Command::Mul(f, s)),
+    ))
 }
 
-impl CompoundToken {
-    fn lift(&self) -> Option<Vec<char>> {
-        match self {
-            CompoundToken::Null => None,
-            CompoundToken::Token(chars) => Some(chars.to_vec())
-        }
-    }
+///////
 
-    fn matches(token: &[SingleToken], string: &str) -> CompoundToken {
-        let num_tokens = token.len();
-        let num_chars = string.chars().count();
-        let mut chars = string.chars();
-        let mut matching = Vec::new();
-        for idx in 0..num_chars.min(num_tokens) {
-            match chars.next() {
-                None => return CompoundToken::Null,
-                Some(char_to_match) => {
-                    if token[idx].is_value(&char_to_match) {
-                        matching.push(char_to_match)
-                    } else {
-                        return CompoundToken::Null
-                    }
-                }
-            }
+fn streamed_answer() {
+    let open = || get_filename_from_args().and_then(|f| fs::File::open(&f).map_err(|_| DataError::CouldNotFindFile(f)));
+    let file = match open() {
+        Err(problem) => {
+            println!("{:?}", problem);
+            return;
         }
-        return CompoundToken::Token(matching);
+        Ok(file) => file
+    };
+    match scanner::Instructions::mul_only().scan(io::BufReader::new(file), false) {
+        Err(problem) => println!("{:?}", problem),
+        Ok(run) => println!("{:?}", run.state.total),
     }
 
-    fn matches_func_while(tokens: &[SingleToken], f: fn(char) -> bool) -> CompoundToken {
-        let mut matching = Vec::new();
-        for token in tokens.iter() {
-            if token.has_meaning() {
-                if f(token.value()) {
-                    matching.push(token.value());   
-                } else {
-                    break;
-                }
-            } else {
-                break;
-            }
+    let file = match open() {
+        Err(problem) => {
+            println!("{:?}", problem);
+            return;
         }
-
-        if matching.is_empty() {
-            CompoundToken::Null
-        } else {    
-            CompoundToken::Token(matching)
+        Ok(file) => file
+    };
+    let show_trace = env::args().any(|arg| arg == "--trace");
+    match scanner::Instructions::standard().scan(io::BufReader::new(file), show_trace) {
+        Err(problem) => println!("{:?}", problem),
+        Ok(run) => {
+            for step in &run.trace {
+                println!("{:>8} {}{:?} -> {}", step.offset, step.name, step.args, step.total);
+            }
+            println!("{:?}", run.state.total);
         }
     }
 }
 
-#[derive(Debug)]
-enum SingleToken {
-    Meaningless,
-    HasMeaning(char)
-}
-
-impl SingleToken {
-    fn new(c: char) -> SingleToken {
-        match c {
-            'm' | 'u' | 'l' | '(' | ',' | ')' | '0'..='9' => SingleToken::HasMeaning(c),
-            'd' | 'o' | 'n' | '\''| 't' => SingleToken::HasMeaning(c),
-            _ => SingleToken::Meaningless
-        }
-    }
-
-    fn has_meaning(&self) -> bool {
-        match self {
-            SingleToken::Meaningless => false,
-            _ => true
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    fn value(&self) -> char {
-        match self {
-            SingleToken::HasMeaning(c) => *c,
-            SingleToken::Meaningless => '\0'
-        }
+    #[test]
+    fn test_enabled_total() {
+        let example = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+        assert_eq!(enabled_total(example), 48);
+        // Four digits is too many, so that one is junk.
+        assert_eq!(enabled_total("mul(1234,5)mul(123,4)"), 492);
+        assert_eq!(parse_mul("mul(12,34)"), (12, 34));
     }
 
-    fn is_value(&self, value: &char) -> bool {
-        match self {
-            SingleToken::HasMeaning(c) => c == value,
-            _ => false
-        }
+    #[test]
+    fn test_instruction_errors() {
+        let problem = instruction()(Input::new("mul(12;3)")).unwrap_err();
+        assert_eq!(problem.position, 6);
+        assert_eq!(problem.expected, "\",\"");
     }
-
 }
@@ -0,0 +1,261 @@
+/* What the SingleToken / CompoundToken thing was trying to be when it grew up.
+ *
+ * A parser is just a function from where we are in the text to either a value
+ * and where we ended up, or an error saying what it wanted and where. Nothing
+ * gets copied, anything that matches text hands back a slice of the input.
+ * Bigger parsers get built by gluing small ones together:
+ *
+ *   tag("mul(")                 exactly this text
+ *   take_while(what, 1, 3, f)   1 to 3 chars that pass f
+ *   seq((a, b, c))              a then b then c, all their values as a tuple
+ *   alt((a, b, c))              the first of a, b or c that works
+ *   many(p)                     p as many times as it'll go
+ *   map(p, f) / try_map(p, f)   do something with p's value
+ *
+ * Only this day and noodling use it so far. Every other day still parses with
+ * split and unwrap, and any of them can pull this in with
+ * #[path = ...] mod parser; the way noodling does.
+ */
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Input<'a> {
+    pub text: &'a str,
+    /// Byte offset into text.
+    pub position: usize,
+}
+
+impl<'a> Input<'a> {
+    pub fn new(text: &'a str) -> Input<'a> {
+        Input { text, position: 0 }
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.text[self.position..]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position >= self.text.len()
+    }
+
+    fn advance(self, bytes: usize) -> Input<'a> {
+        Input { text: self.text, position: self.position + bytes }
+    }
+
+    fn error(&self, expected: impl Into<String>) -> ParseError {
+        ParseError { position: self.position, expected: expected.into() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub position: usize,
+    pub expected: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at byte {}", self.expected, self.position)
+    }
+}
+
+pub type ParseResult<'a, T> = Result<(T, Input<'a>), ParseError>;
+
+/// Run a parser over all of `text`, anything left over afterwards is an error.
+pub fn parse_all<'a, T>(parser: impl Fn(Input<'a>) -> ParseResult<'a, T>, text: &'a str) -> Result<T, ParseError> {
+    let (value, rest) = parser(Input::new(text))?;
+    if !rest.is_empty() {
+        return Err(rest.error("end of input"));
+    }
+    Ok(value)
+}
+
+pub fn tag<'a>(expected: &'static str) -> impl Fn(Input<'a>) -> ParseResult<'a, &'a str> {
+    move |input: Input<'a>| {
+        if input.rest().starts_with(expected) {
+            Ok((&input.rest()[..expected.len()], input.advance(expected.len())))
+        } else {
+            Err(input.error(format!("{:?}", expected)))
+        }
+    }
+}
+
+/// Between `min` and `max` chars that pass `f`, as many as it can get.
+pub fn take_while<'a>(
+    what: &'static str,
+    min: usize,
+    max: usize,
+    f: impl Fn(char) -> bool,
+) -> impl Fn(Input<'a>) -> ParseResult<'a, &'a str> {
+    move |input: Input<'a>| {
+        let rest = input.rest();
+        let (count, bytes) = rest
+            .char_indices()
+            .take(max)
+            .take_while(|&(_, c)| f(c))
+            .fold((0, 0), |(count, _), (i, c)| (count + 1, i + c.len_utf8()));
+        if count < min {
+            return Err(input.error(what));
+        }
+        Ok((&rest[..bytes], input.advance(bytes)))
+    }
+}
+
+/// Any one char at all, only fails at the end of the input.
+pub fn any_char(input: Input<'_>) -> ParseResult<'_, char> {
+    match input.rest().chars().next() {
+        Some(c) => Ok((c, input.advance(c.len_utf8()))),
+        None => Err(input.error("any character")),
+    }
+}
+
+pub fn map<'a, T, U>(
+    parser: impl Fn(Input<'a>) -> ParseResult<'a, T>,
+    f: impl Fn(T) -> U,
+) -> impl Fn(Input<'a>) -> ParseResult<'a, U> {
+    move |input: Input<'a>| parser(input).map(|(value, rest)| (f(value), rest))
+}
+
+/// Like map but f can say no, the error points at where the parser started.
+pub fn try_map<'a, T, U>(
+    parser: impl Fn(Input<'a>) -> ParseResult<'a, T>,
+    f: impl Fn(T) -> Result<U, String>,
+) -> impl Fn(Input<'a>) -> ParseResult<'a, U> {
+    move |input: Input<'a>| {
+        let (value, rest) = parser(input)?;
+        f(value).map(|mapped| (mapped, rest)).map_err(|problem| input.error(problem))
+    }
+}
+
+/// Zero or more. Stops if p matches without moving so it can't spin forever.
+pub fn many<'a, T>(parser: impl Fn(Input<'a>) -> ParseResult<'a, T>) -> impl Fn(Input<'a>) -> ParseResult<'a, Vec<T>> {
+    move |mut input: Input<'a>| {
+        let mut values = Vec::new();
+        while let Ok((value, rest)) = parser(input) {
+            if rest.position == input.position {
+                break;
+            }
+            values.push(value);
+            input = rest;
+        }
+        Ok((values, input))
+    }
+}
+
+pub trait Sequence<'a, T> {
+    fn parse_sequence(&self, input: Input<'a>) -> ParseResult<'a, T>;
+}
+
+pub trait Alternatives<'a, T> {
+    fn parse_first(&self, input: Input<'a>) -> ParseResult<'a, T>;
+}
+
+// Tuples of parsers, (a, b) up to (a, b, c, d, e, f, g). Each entry is the
+// parser's type, its output's type, and names for the parser and its output.
+macro_rules! tuple_parsers {
+    ($($P:ident $V:ident $p:ident $v:ident),+) => {
+        impl<'a, $($V, $P: Fn(Input<'a>) -> ParseResult<'a, $V>),+> Sequence<'a, ($($V,)+)> for ($($P,)+) {
+            fn parse_sequence(&self, input: Input<'a>) -> ParseResult<'a, ($($V,)+)> {
+                let ($($p,)+) = self;
+                $(let ($v, input) = $p(input)?;)+
+                Ok((($($v,)+), input))
+            }
+        }
+
+        impl<'a, T, $($P: Fn(Input<'a>) -> ParseResult<'a, T>),+> Alternatives<'a, T> for ($($P,)+) {
+            fn parse_first(&self, input: Input<'a>) -> ParseResult<'a, T> {
+                let ($($p,)+) = self;
+                let mut furthest: Option<ParseError> = None;
+                $(
+                    match $p(input) {
+                        Ok(found) => return Ok(found),
+                        Err(problem) => furthest = Some(furthest_of(furthest, problem)),
+                    }
+                )+
+                Err(furthest.unwrap())
+            }
+        }
+    };
+}
+
+// Whichever alternative got further is the most useful to complain about, if
+// they tie then say all the things that would have worked.
+fn furthest_of(so_far: Option<ParseError>, problem: ParseError) -> ParseError {
+    match so_far {
+        None => problem,
+        Some(so_far) if so_far.position > problem.position => so_far,
+        Some(so_far) if so_far.position < problem.position => problem,
+        Some(so_far) => ParseError {
+            position: so_far.position,
+            expected: format!("{} or {}", so_far.expected, problem.expected),
+        },
+    }
+}
+
+tuple_parsers!(PA VA a va, PB VB b vb);
+tuple_parsers!(PA VA a va, PB VB b vb, PC VC c vc);
+tuple_parsers!(PA VA a va, PB VB b vb, PC VC c vc, PD VD d vd);
+tuple_parsers!(PA VA a va, PB VB b vb, PC VC c vc, PD VD d vd, PE VE e ve);
+tuple_parsers!(PA VA a va, PB VB b vb, PC VC c vc, PD VD d vd, PE VE e ve, PF VF f vf);
+tuple_parsers!(PA VA a va, PB VB b vb, PC VC c vc, PD VD d vd, PE VE e ve, PF VF f vf, PG VG g vg);
+
+pub fn seq<'a, T>(parsers: impl Sequence<'a, T>) -> impl Fn(Input<'a>) -> ParseResult<'a, T> {
+    move |input: Input<'a>| parsers.parse_sequence(input)
+}
+
+pub fn alt<'a, T>(parsers: impl Alternatives<'a, T>) -> impl Fn(Input<'a>) -> ParseResult<'a, T> {
+    move |input: Input<'a>| parsers.parse_first(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_number<'a>() -> impl Fn(Input<'a>) -> ParseResult<'a, u32> {
+        try_map(take_while("a digit", 1, 3, |c| c.is_ascii_digit()), |digits: &str| {
+            digits.parse().map_err(|_| format!("{} to be a number", digits))
+        })
+    }
+
+    #[test]
+    fn test_building_blocks() {
+        let point = map(seq((small_number(), tag(","), small_number())), |(x, _, y)| (x, y));
+        assert_eq!(parse_all(&point, "12,345"), Ok((12, 345)));
+        assert_eq!(
+            parse_all(&point, "12,3456"),
+            Err(ParseError { position: 6, expected: "end of input".to_string() })
+        );
+        assert_eq!(parse_all(&point, "12;3"), Err(ParseError { position: 2, expected: "\",\"".to_string() }));
+
+        let word = take_while("a letter", 1, usize::MAX, |c| c.is_alphabetic());
+        let (found, rest) = word(Input::new("héllo world")).unwrap();
+        assert_eq!(found, "héllo");
+        assert_eq!(rest.rest(), " world");
+    }
+
+    #[test]
+    fn test_alt_and_many() {
+        let yes_no = alt((map(tag("yes"), |_| true), map(tag("no"), |_| false)));
+        let answers = many(map(seq((&yes_no, tag(" "))), |(answer, _)| answer));
+        let (values, rest) = answers(Input::new("yes no yes maybe")).unwrap();
+        assert_eq!(values, vec![true, false, true]);
+        assert_eq!(rest.position, 11);
+
+        let problem = yes_no(Input::new("maybe")).unwrap_err();
+        assert_eq!(problem.expected, "\"yes\" or \"no\"");
+        // The one that got further wins.
+        let mul_or_do = alt((seq((tag("mul("), small_number(), tag(")"))), seq((tag("do"), small_number(), tag("()")))));
+        assert_eq!(mul_or_do(Input::new("mul(12]")).unwrap_err().position, 6);
+    }
+
+    #[test]
+    fn test_error_positions() {
+        let lines = many(seq((small_number(), tag("\n"))));
+        let text = "1\n22\nx\n";
+        let (_, rest) = lines(Input::new(text)).unwrap();
+        let problem = parse_all(&lines, text).unwrap_err();
+        assert_eq!(problem.position, rest.position);
+        assert_eq!(problem.to_string(), "expected end of input at byte 5");
+    }
+}
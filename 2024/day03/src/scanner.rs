@@ -0,0 +1,297 @@
+/* The corrupted memory scanner with the instructions as data instead of an enum.
+ *
+ * Each instruction is a name, how many numbers go in the parens, how many
+ * digits each number can have, and what it does to the state. mul, do and
+ * don't are just the three that come registered by default, add(a,b) or a
+ * push/pop of the enabled flag is one more .with(...) away.
+ *
+ * Input comes from a reader a chunk at a time so the whole thing never has to
+ * fit in memory. The only trick is the end of a chunk: an instruction can't be
+ * longer than the longest one registered, so anything closer to the end than
+ * that waits for the next chunk before it gets looked at.
+ */
+
+use std::io::{self, Read};
+
+use crate::parser::{seq, tag, take_while, try_map, Input, ParseResult};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// i64 has 19 digits, so 18 always fits.
+const MOST_DIGITS: usize = 18;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct State {
+    pub total: i64,
+    pub disabled: bool,
+    /// For instructions that want to nest, whatever the enabled flag was before.
+    pub saved: Vec<bool>,
+}
+
+/// What an instruction does with its numbers, None if the arithmetic overflowed.
+pub type Effect = fn(&mut State, &[i64]) -> Option<()>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Instruction {
+    pub name: &'static str,
+    pub arity: usize,
+    pub min_digits: usize,
+    pub max_digits: usize,
+    pub effect: Effect,
+}
+
+impl Instruction {
+    // name(1,2,3) at its longest.
+    fn longest(&self) -> usize {
+        self.name.len() + 2 + self.arity * self.max_digits + self.arity.saturating_sub(1)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ScanError {
+    Io(io::ErrorKind),
+    BadDigitLimits(&'static str),
+    Overflow { instruction: &'static str, offset: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// Byte offset of the instruction's first character in the whole input.
+    pub offset: u64,
+    pub name: &'static str,
+    pub args: Vec<i64>,
+    /// The total once the instruction has run.
+    pub total: i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Run {
+    pub state: State,
+    /// Empty unless the scan was asked to keep it.
+    pub trace: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Instructions {
+    known: Vec<Instruction>,
+}
+
+fn mul(state: &mut State, args: &[i64]) -> Option<()> {
+    if !state.disabled {
+        state.total = state.total.checked_add(args[0].checked_mul(args[1])?)?;
+    }
+    Some(())
+}
+
+impl Instructions {
+    pub fn none() -> Instructions {
+        Instructions { known: Vec::new() }
+    }
+
+    // Part 2's mul(a,b), do() and don't().
+    pub fn standard() -> Instructions {
+        Instructions::none()
+            .with(Instruction { name: "mul", arity: 2, min_digits: 1, max_digits: 3, effect: mul })
+            .with(Instruction { name: "do", arity: 0, min_digits: 0, max_digits: 0, effect: |state, _| {
+                state.disabled = false;
+                Some(())
+            } })
+            .with(Instruction { name: "don't", arity: 0, min_digits: 0, max_digits: 0, effect: |state, _| {
+                state.disabled = true;
+                Some(())
+            } })
+    }
+
+    /// Part 1 never turns anything off.
+    pub fn mul_only() -> Instructions {
+        Instructions::none().with(Instruction { name: "mul", arity: 2, min_digits: 1, max_digits: 3, effect: mul })
+    }
+
+    /// Add another instruction, or replace what an existing name does.
+    pub fn with(mut self, instruction: Instruction) -> Instructions {
+        self.known.retain(|known| known.name != instruction.name);
+        self.known.push(instruction);
+        self
+    }
+
+    fn check(&self) -> Result<(), ScanError> {
+        for instruction in &self.known {
+            let Instruction { name, arity, min_digits, max_digits, .. } = *instruction;
+            if arity > 0 && (min_digits == 0 || min_digits > max_digits || max_digits > MOST_DIGITS) {
+                return Err(ScanError::BadDigitLimits(name));
+            }
+        }
+        Ok(())
+    }
+
+    fn longest(&self) -> usize {
+        self.known.iter().map(Instruction::longest).max().unwrap_or(0)
+    }
+
+    // The first registered instruction that parses here, with its numbers.
+    fn match_at<'a>(&self, input: Input<'a>) -> Option<(&Instruction, Vec<i64>, Input<'a>)> {
+        self.known.iter().find_map(|instruction| {
+            let (args, rest) = call(*instruction)(input).ok()?;
+            Some((instruction, args, rest))
+        })
+    }
+
+    /// Run everything in the reader. `keep_trace` records every instruction
+    /// that ran, which for a really big input is a lot of Steps.
+    pub fn scan(&self, mut reader: impl Read, keep_trace: bool) -> Result<Run, ScanError> {
+        self.check()?;
+        let longest = self.longest().max(1);
+        let mut state = State::default();
+        let mut trace = Vec::new();
+
+        // Only ASCII can be part of an instruction, so anything else turns into
+        // junk that's still ASCII. That keeps the buffer a valid str with the
+        // same byte offsets as the input, even when a chunk splits a character.
+        let mut buffer = String::with_capacity(CHUNK_SIZE + longest);
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        let mut buffer_start: u64 = 0;
+        let mut finished = false;
+
+        while !finished {
+            let read = match reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(problem) if problem.kind() == io::ErrorKind::Interrupted => continue,
+                Err(problem) => return Err(ScanError::Io(problem.kind())),
+            };
+            finished = read == 0;
+            buffer.extend(chunk[..read].iter().map(|&b| if b.is_ascii() { b as char } else { '?' }));
+
+            let mut input = Input::new(&buffer);
+            while !input.is_empty() && (finished || input.position + longest <= buffer.len()) {
+                let Some((instruction, args, rest)) = self.match_at(input) else {
+                    input = Input { text: &buffer, position: input.position + 1 };
+                    continue;
+                };
+                let offset = buffer_start + input.position as u64;
+                (instruction.effect)(&mut state, &args)
+                    .ok_or(ScanError::Overflow { instruction: instruction.name, offset })?;
+                if keep_trace {
+                    trace.push(Step { offset, name: instruction.name, args, total: state.total });
+                }
+                input = rest;
+            }
+
+            let done = input.position;
+            buffer.drain(..done);
+            buffer_start += done as u64;
+        }
+
+        Ok(Run { state, trace })
+    }
+}
+
+// name(a,b,...) as a parser, arity isn't known until runtime so no seq here.
+fn call<'a>(instruction: Instruction) -> impl Fn(Input<'a>) -> ParseResult<'a, Vec<i64>> {
+    let number = try_map(
+        take_while("a digit", instruction.min_digits, instruction.max_digits, |c| c.is_ascii_digit()),
+        |digits: &str| digits.parse::<i64>().map_err(|_| format!("{} to be a number", digits)),
+    );
+    move |input: Input<'a>| {
+        let (_, mut input) = seq((tag(instruction.name), tag("(")))(input)?;
+        let mut args = Vec::with_capacity(instruction.arity);
+        for i in 0..instruction.arity {
+            if i > 0 {
+                input = tag(",")(input)?.1;
+            }
+            let (arg, rest) = number(input)?;
+            args.push(arg);
+            input = rest;
+        }
+        let (_, input) = tag(")")(input)?;
+        Ok((args, input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+
+    // Hands out at most `size` bytes per read so instructions get split up.
+    struct Trickle<'a>(&'a [u8], usize);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, into: &mut [u8]) -> io::Result<usize> {
+            let n = self.1.min(self.0.len()).min(into.len());
+            into[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(Instructions::mul_only().scan(EXAMPLE.as_bytes(), false).unwrap().state.total, 161);
+        let run = Instructions::standard().scan(EXAMPLE.as_bytes(), true).unwrap();
+        assert_eq!(run.state.total, 48);
+        let names: Vec<_> = run.trace.iter().map(|step| step.name).collect();
+        assert_eq!(names, ["mul", "don't", "mul", "mul", "do", "mul"]);
+        assert_eq!(run.trace[5], Step { offset: 64, name: "mul", args: vec![8, 5], total: 48 });
+    }
+
+    #[test]
+    fn test_chunk_boundaries() {
+        // Big enough to need a few chunks, with some non ASCII junk for good measure.
+        let text = "é".repeat(CHUNK_SIZE / 3) + &EXAMPLE.repeat(3000);
+        let expected = Instructions::standard().scan(text.as_bytes(), true).unwrap();
+        assert_eq!(expected.state.total, 48 * 3000);
+        for size in [1, 2, 7] {
+            let run = Instructions::standard().scan(Trickle(text.as_bytes(), size), true).unwrap();
+            assert_eq!(run, expected);
+        }
+    }
+
+    #[test]
+    fn test_registered_instructions() {
+        let nested = Instructions::standard()
+            .with(Instruction { name: "add", arity: 2, min_digits: 1, max_digits: 3, effect: |state, args| {
+                if !state.disabled {
+                    state.total = state.total.checked_add(args[0].checked_add(args[1])?)?;
+                }
+                Some(())
+            } })
+            .with(Instruction { name: "push", arity: 1, min_digits: 1, max_digits: 1, effect: |state, args| {
+                state.saved.push(state.disabled);
+                state.disabled = state.disabled || args[0] == 0;
+                Some(())
+            } })
+            .with(Instruction { name: "pop", arity: 0, min_digits: 0, max_digits: 0, effect: |state, _| {
+                state.disabled = state.saved.pop().unwrap_or(false);
+                Some(())
+            } });
+        let text = "add(1,2)push(0)mul(9,9)push(1)add(5,5)pop()pop()mul(2,3)add(1,2,3)";
+        let run = nested.scan(text.as_bytes(), false).unwrap();
+        assert_eq!(run.state.total, 3 + 6);
+        assert!(run.state.saved.is_empty());
+    }
+
+    #[test]
+    fn test_problems() {
+        let big = Instructions::none().with(Instruction {
+            name: "mul",
+            arity: 2,
+            min_digits: 1,
+            max_digits: MOST_DIGITS,
+            effect: mul,
+        });
+        let text = "mul(999999999,999999999)xmul(999999999999999999,99)";
+        assert_eq!(
+            big.scan(text.as_bytes(), false),
+            Err(ScanError::Overflow { instruction: "mul", offset: 25 })
+        );
+        let broken = Instructions::none().with(Instruction {
+            name: "f",
+            arity: 1,
+            min_digits: 0,
+            max_digits: 3,
+            effect: |_, _| Some(()),
+        });
+        assert_eq!(broken.scan("f()".as_bytes(), false), Err(ScanError::BadDigitLimits("f")));
+    }
+}
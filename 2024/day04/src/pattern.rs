@@ -0,0 +1,179 @@
+/* Word search, but the "word" is any little 2D picture with don't-care cells.
+ *
+ *   XMAS        X...        M.S
+ *               .M..        .A.
+ *               ..A.        M.S
+ *               ...S
+ *
+ * A template gets tried in every rotation and mirror image, so XMAS only has
+ * to be written forwards once. Some of those 8 come out the same (XMAS turned
+ * twice then mirrored is just XMAS again) and those only get counted once,
+ * otherwise every match would show up twice.
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symmetry {
+    /// Clockwise, after any mirroring.
+    pub quarter_turns: u8,
+    /// Left and right swapped.
+    pub mirrored: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// None matches anything.
+    cells: Vec<Vec<Option<char>>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Where the template's top left cell landed.
+    pub row: usize,
+    pub col: usize,
+    pub symmetry: Symmetry,
+}
+
+impl Template {
+    /// One line per row, `wildcard` for the cells that can be anything.
+    pub fn parse(picture: &str, wildcard: char) -> Template {
+        let cells = picture
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().map(|c| if c == wildcard { None } else { Some(c) }).collect())
+            .collect();
+        Template { cells }
+    }
+
+    /// 1 by N, left to right.
+    pub fn word(word: &str) -> Template {
+        Template { cells: vec![word.chars().map(Some).collect()] }
+    }
+
+    /// N by N with the word down the diagonal and wildcards everywhere else.
+    pub fn diagonal(word: &str) -> Template {
+        let n = word.chars().count();
+        let mut cells = vec![vec![None; n]; n];
+        for (i, c) in word.chars().enumerate() {
+            cells[i][i] = Some(c);
+        }
+        Template { cells }
+    }
+
+    fn height(&self) -> usize {
+        self.cells.len()
+    }
+
+    fn width(&self) -> usize {
+        self.cells.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn mirror(&self) -> Template {
+        let width = self.width();
+        let cells = self
+            .cells
+            .iter()
+            .map(|row| (0..width).rev().map(|c| row.get(c).copied().flatten()).collect())
+            .collect();
+        Template { cells }
+    }
+
+    fn turn(&self) -> Template {
+        let (height, width) = (self.height(), self.width());
+        let cells = (0..width)
+            .map(|r| (0..height).map(|c| self.cells[height - 1 - c].get(r).copied().flatten()).collect())
+            .collect();
+        Template { cells }
+    }
+
+    /// Every different way this template can be turned or flipped, each once.
+    pub fn orientations(&self) -> Vec<(Symmetry, Template)> {
+        let mut found: Vec<(Symmetry, Template)> = Vec::with_capacity(8);
+        for mirrored in [false, true] {
+            let mut turned = if mirrored { self.mirror() } else { self.clone() };
+            for quarter_turns in 0..4 {
+                if !found.iter().any(|(_, seen)| *seen == turned) {
+                    found.push((Symmetry { quarter_turns, mirrored }, turned.clone()));
+                }
+                turned = turned.turn();
+            }
+        }
+        found
+    }
+
+    fn matches_at(&self, grid: &[Vec<char>], row: usize, col: usize) -> bool {
+        self.cells.iter().enumerate().all(|(r, cells)| {
+            cells.iter().enumerate().all(|(c, wanted)| match wanted {
+                None => true,
+                Some(wanted) => grid.get(row + r).and_then(|line| line.get(col + c)) == Some(wanted),
+            })
+        })
+    }
+
+    /// Every place any orientation of the template fits, in reading order.
+    pub fn find(&self, grid: &[Vec<char>]) -> Vec<Match> {
+        let rows = grid.len();
+        let cols = grid.iter().map(Vec::len).max().unwrap_or(0);
+        let mut found = Vec::new();
+        for (symmetry, template) in self.orientations() {
+            for row in 0..(rows + 1).saturating_sub(template.height()) {
+                for col in 0..(cols + 1).saturating_sub(template.width()) {
+                    if template.matches_at(grid, row, col) {
+                        found.push(Match { row, col, symmetry });
+                    }
+                }
+            }
+        }
+        found.sort_by_key(|m| (m.row, m.col));
+        found
+    }
+
+    pub fn count(&self, grid: &[Vec<char>]) -> usize {
+        self.find(grid).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "MMMSXXMASM\nMSAMXMSMSA\nAMXSXMAAMM\nMSAMASMSMX\nXMASAMXAMM\nXXAMMXXAMA\nSMSMSASXSS\nSAXAMASAAA\nMAMMMXMMMM\nMXMXAXMASX";
+
+    fn grid(raw: &str) -> Vec<Vec<char>> {
+        raw.lines().map(|line| line.chars().collect()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let grid = grid(EXAMPLE);
+        assert_eq!(Template::word("XMAS").count(&grid) + Template::diagonal("XMAS").count(&grid), 18);
+        assert_eq!(Template::parse("M.S\n.A.\nM.S", '.').count(&grid), 9);
+    }
+
+    #[test]
+    fn test_orientations() {
+        assert_eq!(Template::word("XMAS").orientations().len(), 4);
+        assert_eq!(Template::word("ABA").orientations().len(), 2);
+        assert_eq!(Template::parse("M.S\n.A.\nM.S", '.').orientations().len(), 4);
+        // An L has no symmetry at all so all 8 are different.
+        assert_eq!(Template::parse("X..\nXXX", '.').orientations().len(), 8);
+
+        let turned = Template::parse("AB\nCD\nEF", '.').turn();
+        assert_eq!(turned, Template::parse("ECA\nFDB", '.'));
+        assert_eq!(turned.turn().turn().turn(), Template::parse("AB\nCD\nEF", '.'));
+    }
+
+    #[test]
+    fn test_find() {
+        let search = grid("SAMX\n.A..\nXMAS");
+        let found = Template::word("XMAS").find(&search);
+        assert_eq!(
+            found,
+            vec![
+                Match { row: 0, col: 0, symmetry: Symmetry { quarter_turns: 2, mirrored: false } },
+                Match { row: 2, col: 0, symmetry: Symmetry { quarter_turns: 0, mirrored: false } },
+            ]
+        );
+        // Ragged rows just don't match past their end.
+        assert_eq!(Template::word("AS").count(&grid(".AS\nA\n")), 1);
+    }
+}
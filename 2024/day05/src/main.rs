@@ -1,18 +1,23 @@
 use std::fs;
-use std::cmp::Ordering;
+
+mod rules;
+use rules::Rules;
 
 fn main() {
     let raw_data = fs::read_to_string("../input.txt").expect("bad input data");
-    let ordering = build_ordering(&raw_data);
+    let rules = Rules::new(&build_ordering(&raw_data));
     let reports = build_reports(&raw_data);
 
     let mut correctly_ordered = Vec::new();
     let mut incorrectly_ordered = Vec::new();
     for report in reports.iter() {
-        if report.is_valid_according_to(&ordering) {
-            correctly_ordered.push(report);
-        } else {
-            incorrectly_ordered.push(report);
+        match rules.is_valid(&report.data) {
+            Ok(true) => correctly_ordered.push(report),
+            Ok(false) => incorrectly_ordered.push(report),
+            Err(problem) => {
+                println!("{:?} in {:?}", problem, report);
+                return;
+            }
         }
     }
 
@@ -23,12 +28,26 @@ fn main() {
     println!("Correct: {:?}", sum_of_middles);
 
     let mut sum_of_middles = 0;
+    let mut ambiguous = 0;
     for report in incorrectly_ordered.iter() {
-        let fixed = report.re_order_according_to(&ordering);
-        sum_of_middles += fixed.middle_number();
+        let fixed = match rules.order(&report.data) {
+            Ok(fixed) => fixed,
+            Err(problem) => {
+                println!("{:?} in {:?}", problem, report);
+                return;
+            }
+        };
+        if fixed.ambiguous {
+            ambiguous += 1;
+        }
+        sum_of_middles += Report { data: fixed.pages }.middle_number();
     }
 
     println!("Incorrect: {:?}", sum_of_middles);
+    if ambiguous > 0 {
+        // The middle page might not be the same in every valid order for these.
+        println!("{:?} of those could be ordered more than one way", ambiguous);
+    }
 }
 
 #[derive(Debug)]
@@ -37,43 +56,10 @@ struct Report {
 }
 
 impl Report {
-    fn is_valid_according_to(&self, ordering: &[(i32, i32)]) -> bool {
-        let mut valid = true;
-        for idx in 0..self.data.len() {
-            let report = self.data[idx];
-            let ordering = ordering.iter().filter(|o| o.0 == report);
-            let must_appear_after: Vec<i32> = ordering.map(|o| o.1).collect();
-            for order in must_appear_after.iter() {
-                match self.data.iter().position(|&r| r == *order) {
-                    None => {}, // If the right side is not in the report then ignore it.
-                    Some(location) => {
-                        valid = valid && idx < location;
-                    }
-                }
-            }
-        }
-        valid
-    }
-
     fn middle_number(&self) -> i32 {
         let mid = self.data.len() / 2;
         self.data[mid]
     }
-
-    fn re_order_according_to(&self, ordering: &[(i32, i32)]) -> Report {
-        let mut new_data = self.data.clone();
-        new_data.sort_by(|a,b| {
-            for (before, after) in ordering.iter() {
-                if a == before && b == after {
-                    return Ordering::Less
-                } else if b == before && a == after {
-                    return Ordering::Greater
-                }
-            }
-            Ordering::Equal
-        });
-        Report { data: new_data.clone() }
-    }
 }
 
 fn build_reports(raw_data: &str) -> Vec<Report> {
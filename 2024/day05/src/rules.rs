@@ -0,0 +1,177 @@
+/* The page ordering rules as a graph, an X|Y rule is an edge X -> Y.
+ *
+ * Sorting with a comparator over the rules only worked because the puzzle is
+ * nice and every pair of pages in an update has a rule between them. Without
+ * that the comparator isn't a total order and sort can give back anything. So
+ * instead, for each update only look at the rules where both pages are in it
+ * and topologically sort that (Kahn's algorithm):
+ *
+ *  - take any page nothing is waiting on, put it next, drop its edges, repeat
+ *  - if there's ever more than one page to pick from, more than one order works
+ *  - if pages are left over that are all waiting on each other, that's a cycle
+ *
+ * https://en.wikipedia.org/wiki/Topological_sorting#Kahn's_algorithm
+ */
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+#[derive(Debug, PartialEq)]
+pub enum RuleError {
+    RepeatedPage(i32),
+    /// The pages going round the cycle, each one has to come before the next
+    /// and the last has to come before the first.
+    Cycle(Vec<i32>),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Ordered {
+    pub pages: Vec<i32>,
+    /// More than one order follows the rules, pages are kept in the order the
+    /// update had them wherever the rules don't say otherwise.
+    pub ambiguous: bool,
+}
+
+#[derive(Debug)]
+pub struct Rules {
+    // page -> every page that has to come after it
+    before: HashMap<i32, Vec<i32>>,
+}
+
+impl Rules {
+    pub fn new(ordering: &[(i32, i32)]) -> Rules {
+        let mut before: HashMap<i32, Vec<i32>> = HashMap::new();
+        for &(first, second) in ordering {
+            before.entry(first).or_default().push(second);
+        }
+        Rules { before }
+    }
+
+    fn positions(update: &[i32]) -> Result<HashMap<i32, usize>, RuleError> {
+        let mut positions = HashMap::with_capacity(update.len());
+        for (i, &page) in update.iter().enumerate() {
+            if positions.insert(page, i).is_some() {
+                return Err(RuleError::RepeatedPage(page));
+            }
+        }
+        Ok(positions)
+    }
+
+    // Each rule between two pages in the update, as positions in the update.
+    fn edges<'a>(&'a self, update: &'a [i32], positions: &'a HashMap<i32, usize>) -> impl Iterator<Item = (usize, usize)> + 'a {
+        update.iter().enumerate().flat_map(move |(from, page)| {
+            self.before
+                .get(page)
+                .into_iter()
+                .flatten()
+                .filter_map(move |after| positions.get(after).map(|&to| (from, to)))
+        })
+    }
+
+    /// Does every rule between pages in the update hold.
+    pub fn is_valid(&self, update: &[i32]) -> Result<bool, RuleError> {
+        let positions = Rules::positions(update)?;
+        let valid = self.edges(update, &positions).all(|(from, to)| from < to);
+        Ok(valid)
+    }
+
+    /// The update sorted so every rule holds.
+    pub fn order(&self, update: &[i32]) -> Result<Ordered, RuleError> {
+        let positions = Rules::positions(update)?;
+        let mut after = vec![Vec::new(); update.len()];
+        let mut waiting_on = vec![0; update.len()];
+        for (from, to) in self.edges(update, &positions) {
+            after[from].push(to);
+            waiting_on[to] += 1;
+        }
+
+        // Smallest position first, so the update's own order breaks ties.
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..update.len()).filter(|&i| waiting_on[i] == 0).map(Reverse).collect();
+        let mut pages = Vec::with_capacity(update.len());
+        let mut ambiguous = false;
+        while let Some(Reverse(next)) = ready.pop() {
+            ambiguous |= !ready.is_empty();
+            pages.push(update[next]);
+            for &to in &after[next] {
+                waiting_on[to] -= 1;
+                if waiting_on[to] == 0 {
+                    ready.push(Reverse(to));
+                }
+            }
+        }
+
+        if pages.len() < update.len() {
+            return Err(RuleError::Cycle(find_cycle(update, &after, &waiting_on)));
+        }
+        Ok(Ordered { pages, ambiguous })
+    }
+}
+
+// Everything still waiting on something is waiting on another page that's
+// still waiting too, so walking backwards from any of them has to loop.
+fn find_cycle(update: &[i32], after: &[Vec<usize>], waiting_on: &[usize]) -> Vec<i32> {
+    let mut waiting_for: Vec<Option<usize>> = vec![None; update.len()];
+    for (from, tos) in after.iter().enumerate() {
+        if waiting_on[from] == 0 {
+            continue;
+        }
+        for &to in tos {
+            waiting_for[to] = Some(from);
+        }
+    }
+
+    let mut step = vec![usize::MAX; update.len()];
+    let mut walk = Vec::new();
+    let mut at = (0..update.len()).find(|&i| waiting_on[i] > 0).unwrap();
+    while step[at] == usize::MAX {
+        step[at] = walk.len();
+        walk.push(at);
+        at = waiting_for[at].unwrap();
+    }
+    let mut cycle: Vec<i32> = walk[step[at]..].iter().map(|&i| update[i]).collect();
+    cycle.reverse();
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_RULES: [(i32, i32); 21] = [
+        (47, 53), (97, 13), (97, 61), (97, 47), (75, 29), (61, 13), (75, 53), (29, 13), (97, 29), (53, 29), (61, 53),
+        (97, 53), (61, 29), (47, 13), (75, 47), (97, 75), (47, 61), (75, 61), (47, 29), (75, 13), (53, 13),
+    ];
+
+    #[test]
+    fn test_example() {
+        let rules = Rules::new(&EXAMPLE_RULES);
+        assert_eq!(rules.is_valid(&[75, 47, 61, 53, 29]), Ok(true));
+        assert_eq!(rules.is_valid(&[75, 97, 47, 61, 53]), Ok(false));
+        assert_eq!(rules.is_valid(&[61, 13, 29]), Ok(false));
+
+        let fixed = rules.order(&[75, 97, 47, 61, 53]).unwrap();
+        assert_eq!(fixed, Ordered { pages: vec![97, 75, 47, 61, 53], ambiguous: false });
+        assert_eq!(rules.order(&[61, 13, 29]).unwrap().pages, vec![61, 29, 13]);
+        assert_eq!(rules.order(&[97, 13, 75, 29, 47]).unwrap().pages, vec![97, 75, 47, 29, 13]);
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        // Nothing says where 5 goes relative to 1 or 2.
+        let rules = Rules::new(&[(1, 2), (2, 3)]);
+        let ordered = rules.order(&[3, 5, 2, 1]).unwrap();
+        assert_eq!(ordered, Ordered { pages: vec![5, 1, 2, 3], ambiguous: true });
+        assert!(!rules.order(&[3, 1, 2]).unwrap().ambiguous);
+        // Rules about pages that aren't in the update don't count.
+        assert_eq!(rules.is_valid(&[1, 3]), Ok(true));
+    }
+
+    #[test]
+    fn test_problems() {
+        let rules = Rules::new(&[(1, 2), (2, 3), (3, 4), (4, 2), (9, 1)]);
+        assert_eq!(rules.order(&[9, 4, 1, 3, 2]), Err(RuleError::Cycle(vec![2, 3, 4])));
+        // The cycle only matters if all of it is in the update.
+        assert_eq!(rules.order(&[4, 3, 1]).unwrap().pages, vec![3, 4, 1]);
+        assert_eq!(rules.is_valid(&[1, 2, 1]), Err(RuleError::RepeatedPage(1)));
+    }
+}
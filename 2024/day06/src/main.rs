@@ -1,30 +1,37 @@
+use std::env;
 use std::fs;
+use std::thread;
+
+mod patrol;
+use patrol::Lab;
 
 fn main() {
     let raw_data = fs::read_to_string("../sample.txt").expect("bad input data");
     let raw_data = raw_data.as_str();
 
-    // Uncomment this to generate anim.txt file input
-    // generate_animation_txt(raw_data);
-    // but comment out the rest below when you generate it
-    
-    let (matrix, rows, cols) = make_matrix(raw_data);
-    let mut elven_mischief_possible = 0;
-    for row in 0..rows {
-        for col in 0..cols {
-            if is_guard(&matrix[row][col]) {
-                continue;
-            }
-            // Mark a spot as an obstacle
-            let mut fiddle = matrix.clone();
-            fiddle[row][col] = '#';
-            match watch_guard_or_cycle(fiddle, rows, cols, false) {
-                None => elven_mischief_possible += 1,
-                Some(wait_time) => {} // println!("Position ({}, {}) lets the guard leave in {:?} steps", row, col, wait_time),
-            }
+    // Run with --animate > anim.txt to generate the input for animation.rs
+    if env::args().any(|arg| arg == "--animate") {
+        generate_animation_txt(raw_data);
+        return;
+    }
+
+    let lab = match Lab::try_from(raw_data) {
+        Err(problem) => {
+            println!("{:?}", problem);
+            return;
         }
+        Ok(lab) => lab
+    };
+    let patrol = lab.walk();
+    if !patrol.escaped {
+        println!("The guard never leaves!");
+        return;
     }
-    println!("Elven mischief possible {}", elven_mischief_possible);
+    println!("Guard visits {}", patrol.visited.len());
+
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mischief = lab.loop_positions(&patrol, threads);
+    println!("Elven mischief possible {}", mischief.len());
 }
 
 fn generate_animation_txt(raw_data: &str) {
@@ -0,0 +1,348 @@
+/* The guard only ever does something interesting when they hit an obstacle,
+ * so rather than stepping one cell at a time, work out ahead of time where
+ * they'd stop walking from every cell in every direction (the jump tables) and
+ * go obstacle to obstacle.
+ *
+ * For part 2 the new obstacle can only matter if it's somewhere they walk, and
+ * everything they do before first getting there stays the same. So each loop
+ * check starts from the step before they'd walk into it, and the one extra
+ * obstacle gets checked by hand on each jump instead of copying the lab.
+ */
+
+use std::thread;
+
+pub type Position = (usize, usize);
+
+// They walk off the edge instead of stopping anywhere.
+const EXIT: usize = usize::MAX;
+
+#[derive(Debug, PartialEq)]
+pub enum LabError {
+    NoGuard,
+    MultipleGuards,
+    RaggedRow(usize),
+    UnknownCell { row: usize, col: usize, symbol: char },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heading {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Heading {
+    const ALL: [Heading; 4] = [Heading::Up, Heading::Right, Heading::Down, Heading::Left];
+
+    fn of(symbol: char) -> Option<Heading> {
+        match symbol {
+            '^' => Some(Heading::Up),
+            '>' => Some(Heading::Right),
+            'v' => Some(Heading::Down),
+            '<' => Some(Heading::Left),
+            _ => None,
+        }
+    }
+
+    fn turn_right(self) -> Heading {
+        Heading::ALL[(self as usize + 1) % 4]
+    }
+}
+
+#[derive(Debug)]
+pub struct Lab {
+    rows: usize,
+    cols: usize,
+    obstacles: Vec<bool>,
+    start: usize,
+    facing: Heading,
+    // jumps[heading][cell] is where the guard stops walking that way from cell.
+    jumps: [Vec<usize>; 4],
+}
+
+#[derive(Debug)]
+pub struct Patrol {
+    /// Every cell the guard stands on, in the order they first get to them.
+    pub visited: Vec<Position>,
+    /// False if they walk in circles forever instead of leaving.
+    pub escaped: bool,
+    // Where the guard was and which way they were facing the step before first getting
+    // to visited[i + 1].
+    before: Vec<(usize, Heading)>,
+}
+
+impl TryFrom<&str> for Lab {
+    type Error = LabError;
+
+    fn try_from(raw_data: &str) -> Result<Lab, LabError> {
+        let lines: Vec<&str> = raw_data.lines().filter(|line| !line.is_empty()).collect();
+        let rows = lines.len();
+        let cols = lines.first().map_or(0, |line| line.chars().count());
+
+        let mut obstacles = Vec::with_capacity(rows * cols);
+        let mut guard = None;
+        for (row, line) in lines.iter().enumerate() {
+            if line.chars().count() != cols {
+                return Err(LabError::RaggedRow(row));
+            }
+            for (col, symbol) in line.chars().enumerate() {
+                match (symbol, Heading::of(symbol)) {
+                    ('#', _) => obstacles.push(true),
+                    ('.', _) => obstacles.push(false),
+                    (_, Some(heading)) => {
+                        if guard.is_some() {
+                            return Err(LabError::MultipleGuards);
+                        }
+                        guard = Some((row * cols + col, heading));
+                        obstacles.push(false);
+                    }
+                    (_, None) => return Err(LabError::UnknownCell { row, col, symbol }),
+                }
+            }
+        }
+        let (start, facing) = guard.ok_or(LabError::NoGuard)?;
+
+        let mut lab = Lab { rows, cols, obstacles, start, facing, jumps: Default::default() };
+        lab.jumps = Heading::ALL.map(|heading| lab.jump_table(heading));
+        Ok(lab)
+    }
+}
+
+impl Lab {
+    fn position(&self, cell: usize) -> Position {
+        (cell / self.cols, cell % self.cols)
+    }
+
+    // The cell one step along, None if that's off the edge.
+    fn step(&self, cell: usize, heading: Heading) -> Option<usize> {
+        let (row, col) = self.position(cell);
+        match heading {
+            Heading::Up if row > 0 => Some(cell - self.cols),
+            Heading::Down if row + 1 < self.rows => Some(cell + self.cols),
+            Heading::Left if col > 0 => Some(cell - 1),
+            Heading::Right if col + 1 < self.cols => Some(cell + 1),
+            _ => None,
+        }
+    }
+
+    // Sweep each line from the far end back, so every cell's stop is either
+    // itself (an obstacle just ahead) or the stop of the cell ahead of it.
+    fn jump_table(&self, heading: Heading) -> Vec<usize> {
+        let mut table = vec![EXIT; self.rows * self.cols];
+        let mut cells: Vec<usize> = (0..self.rows * self.cols).collect();
+        if matches!(heading, Heading::Down | Heading::Right) {
+            cells.reverse();
+        }
+        for cell in cells {
+            table[cell] = match self.step(cell, heading) {
+                None => EXIT,
+                Some(ahead) if self.obstacles[ahead] => cell,
+                Some(ahead) => table[ahead],
+            };
+        }
+        table
+    }
+
+    // Where the guard stops walking from cell, with one more obstacle at extra.
+    fn jump(&self, cell: usize, heading: Heading, extra: usize) -> usize {
+        let stop = self.jumps[heading as usize][cell];
+        let ((row, col), (extra_row, extra_col)) = (self.position(cell), self.position(extra));
+        let (stop_row, stop_col) = if stop == EXIT { (usize::MAX, usize::MAX) } else { self.position(stop) };
+        let in_the_way = match heading {
+            Heading::Up => extra_col == col && extra_row < row && (stop == EXIT || extra_row >= stop_row),
+            Heading::Down => extra_col == col && extra_row > row && (stop == EXIT || extra_row <= stop_row),
+            Heading::Left => extra_row == row && extra_col < col && (stop == EXIT || extra_col >= stop_col),
+            Heading::Right => extra_row == row && extra_col > col && (stop == EXIT || extra_col <= stop_col),
+        };
+        if !in_the_way {
+            return stop;
+        }
+        // One step back from the new obstacle, towards the guard.
+        self.step(extra, heading.turn_right().turn_right()).unwrap()
+    }
+
+    /// The guard's whole walk one cell at a time, for part 1 and to know where the
+    /// new obstacles could go.
+    pub fn walk(&self) -> Patrol {
+        let mut seen_cell = vec![false; self.obstacles.len()];
+        let mut seen_state = vec![false; self.obstacles.len() * 4];
+        let (mut at, mut heading) = (self.start, self.facing);
+        let mut visited = vec![self.position(at)];
+        let mut before = Vec::new();
+        seen_cell[at] = true;
+
+        loop {
+            if std::mem::replace(&mut seen_state[at * 4 + heading as usize], true) {
+                return Patrol { visited, escaped: false, before };
+            }
+            match self.step(at, heading) {
+                None => return Patrol { visited, escaped: true, before },
+                Some(ahead) if self.obstacles[ahead] => heading = heading.turn_right(),
+                Some(ahead) => {
+                    if !seen_cell[ahead] {
+                        seen_cell[ahead] = true;
+                        visited.push(self.position(ahead));
+                        before.push((at, heading));
+                    }
+                    at = ahead;
+                }
+            }
+        }
+    }
+
+    // Obstacle to obstacle from the step before extra until they leave or are
+    // somewhere they've already been facing the same way. seen[state] == stamp
+    // means seen this check, so the buffer never has to be cleared.
+    fn loops_with(&self, extra: usize, (mut at, mut heading): (usize, Heading), seen: &mut [u32], stamp: u32) -> bool {
+        loop {
+            at = self.jump(at, heading, extra);
+            if at == EXIT {
+                return false;
+            }
+            heading = heading.turn_right();
+            let state = at * 4 + heading as usize;
+            if seen[state] == stamp {
+                return true;
+            }
+            seen[state] = stamp;
+        }
+    }
+
+    /// Every spot a new obstacle would send the guard round in a loop forever,
+    /// split between `threads` threads, in the order they first walk there.
+    pub fn loop_positions(&self, patrol: &Patrol, threads: usize) -> Vec<Position> {
+        let candidates: Vec<(usize, (usize, Heading))> = patrol.visited[1..]
+            .iter()
+            .zip(&patrol.before)
+            .map(|(&(row, col), &before)| (row * self.cols + col, before))
+            .collect();
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+
+        let chunk_size = candidates.len().div_ceil(threads.max(1));
+        thread::scope(|scope| {
+            let workers: Vec<_> = candidates
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        let mut seen = vec![0u32; self.obstacles.len() * 4];
+                        chunk
+                            .iter()
+                            .enumerate()
+                            .filter(|&(i, &(extra, before))| self.loops_with(extra, before, &mut seen, i as u32 + 1))
+                            .map(|(_, &(extra, _))| self.position(extra))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers.into_iter().flat_map(|worker| worker.join().unwrap()).collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "....#.....\n.........#\n..........\n..#.......\n.......#..\n..........\n.#..^.....\n........#.\n#.........\n......#...";
+
+    // One cell at a time on a copy of the lab, like it used to be done.
+    fn brute_force_loops(lab: &Lab, extra: usize) -> bool {
+        let mut obstacles = lab.obstacles.clone();
+        obstacles[extra] = true;
+        let mut seen = vec![false; obstacles.len() * 4];
+        let (mut at, mut heading) = (lab.start, lab.facing);
+        loop {
+            if std::mem::replace(&mut seen[at * 4 + heading as usize], true) {
+                return true;
+            }
+            match lab.step(at, heading) {
+                None => return false,
+                Some(ahead) if obstacles[ahead] => heading = heading.turn_right(),
+                Some(ahead) => at = ahead,
+            }
+        }
+    }
+
+    fn brute_force_positions(lab: &Lab) -> Vec<Position> {
+        let mut found: Vec<Position> = (0..lab.obstacles.len())
+            .filter(|&cell| cell != lab.start && !lab.obstacles[cell] && brute_force_loops(lab, cell))
+            .map(|cell| lab.position(cell))
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_example() {
+        let lab = Lab::try_from(EXAMPLE).unwrap();
+        let patrol = lab.walk();
+        assert!(patrol.escaped);
+        assert_eq!(patrol.visited.len(), 41);
+        let positions = lab.loop_positions(&patrol, 3);
+        assert_eq!(positions.len(), 6);
+        assert!(positions.contains(&(6, 3)));
+        let mut sorted = positions.clone();
+        sorted.sort();
+        assert_eq!(sorted, brute_force_positions(&lab));
+        assert_eq!(lab.loop_positions(&patrol, 1), positions);
+    }
+
+    fn loop_positions(raw: &str, threads: usize) -> (usize, Vec<Position>) {
+        let lab = Lab::try_from(raw).unwrap();
+        let patrol = lab.walk();
+        assert!(patrol.escaped);
+        let mut positions = lab.loop_positions(&patrol, threads);
+        positions.sort();
+        (patrol.visited.len(), positions)
+    }
+
+    #[test]
+    fn test_crossing_own_path() {
+        // The guard goes right along row 1 and later comes back left over it, an
+        // obstacle at (1, 3) has to be tried from the first time through.
+        assert_eq!(loop_positions(".#.#..\n.....#\n.^..#.\n#..#..\n..#...", 4), (6, vec![(1, 0), (1, 3)]));
+        // Up column 1, bounced off the wall and back down it.
+        assert_eq!(
+            loop_positions("###...\n..#..#\n....##\n#.<.#.\n...#..", 4),
+            (5, vec![(1, 1), (2, 1), (3, 1), (4, 1)])
+        );
+    }
+
+    #[test]
+    fn test_nowhere_to_loop() {
+        assert_eq!(loop_positions("#...\n...#\n>...\n..#.", 4), (4, vec![]));
+        assert_eq!(loop_positions("..#..\n.....\n..^..\n.....\n.....", 4), (4, vec![]));
+        // Out on the first move, so nothing at all to try.
+        assert_eq!(loop_positions("...\n..>\n...", 4), (1, vec![]));
+    }
+
+    #[test]
+    fn test_more_threads_than_cells() {
+        let lab = ".#.#..\n.....#\n.^..#.\n#..#..\n..#...";
+        assert_eq!(loop_positions(lab, 64), loop_positions(lab, 1));
+        assert_eq!(loop_positions("...\n..>\n...", 64), (1, vec![]));
+    }
+
+    #[test]
+    fn test_jump_tables() {
+        let lab = Lab::try_from("..#\n>..\n#..").unwrap();
+        assert_eq!(lab.jumps[Heading::Right as usize][3], EXIT);
+        assert_eq!(lab.jumps[Heading::Up as usize][5], 5);
+        assert_eq!(lab.jumps[Heading::Down as usize][3], 3);
+        assert_eq!(lab.jumps[Heading::Up as usize][8], 5);
+        // An extra obstacle at the end of the middle row stops the guard next to it.
+        assert_eq!(lab.jump(3, Heading::Right, 5), 4);
+        assert_eq!(lab.jump(3, Heading::Right, 0), EXIT);
+    }
+
+    #[test]
+    fn test_bad_labs() {
+        assert_eq!(Lab::try_from("...\n...").unwrap_err(), LabError::NoGuard);
+        assert_eq!(Lab::try_from("^.>").unwrap_err(), LabError::MultipleGuards);
+        assert_eq!(Lab::try_from("^..\n..").unwrap_err(), LabError::RaggedRow(1));
+        assert_eq!(Lab::try_from("^.O").unwrap_err(), LabError::UnknownCell { row: 0, col: 2, symbol: 'O' });
+    }
+}
@@ -0,0 +1,321 @@
+/* Calibrations and the operators that can go between their numbers.
+ */
+
+#[derive(Debug, Clone)]
+pub struct Calibration {
+    pub result: u64,
+    pub numbers: Vec<u64>
+}
+
+impl Calibration {
+    pub fn new(result: u64, numbers: Vec<u64>) -> Calibration {
+        Calibration {
+            result,
+            numbers
+        }
+    }
+
+    pub fn from(line: &str) -> Calibration {
+        let mut iter = line.split(":");
+        let result = iter.next().unwrap().parse().unwrap();
+        let numbers = iter.next().unwrap().split(" ").filter(|s| !s.is_empty()).map(|n| {
+            n.parse().unwrap()
+        }).collect();
+        Calibration::new(result, numbers)
+    }
+
+    /* Going forwards means trying every operator in every gap, 3^(n-1) of
+     * them. Going backwards from the result is much pickier: the last number
+     * can only have been added if the result is at least that big, multiplied
+     * if it divides the result, concatenated if the result ends with it. Each
+     * of those says exactly what everything before it had to come to, and most
+     * of the time only one or none of them work so the search barely branches.
+     */
+    fn peel(&self, operators: &[Operand], upto: usize, target: u64, chain: &mut Vec<Operand>, found: &mut Vec<Vec<Operand>>, all: bool) {
+        if upto == 0 {
+            if target == self.numbers[0] {
+                // chain was built last operator first
+                found.push(chain.iter().rev().copied().collect());
+            }
+            return;
+        }
+        for operator in operators {
+            if !all && !found.is_empty() {
+                return;
+            }
+            let undone = match operator {
+                // A custom undo only has room for one left side, so when every
+                // way is wanted check each left side going forwards instead.
+                Operand::Other(_) if all => Undone::AnyLeft,
+                _ => operator.undo(target, self.numbers[upto]),
+            };
+            match undone {
+                Undone::Nothing => {}
+                Undone::Left(left) => {
+                    chain.push(*operator);
+                    self.peel(operators, upto - 1, left, chain, found, all);
+                    chain.pop();
+                }
+                Undone::AnyLeft => {
+                    chain.push(*operator);
+                    let right = self.numbers[upto];
+                    self.grow(operators, upto - 1, self.numbers[0], &mut Vec::new(), &mut |prefix, left| {
+                        if operator.apply(left, right) == Some(target) {
+                            found.push(prefix.iter().chain(chain.iter().rev()).copied().collect());
+                        }
+                        all || found.is_empty()
+                    });
+                    chain.pop();
+                }
+            }
+        }
+    }
+
+    // Backwards has nothing to go on once any left side might do, so go
+    // forwards from the front instead: finish gets every prefix that gets
+    // through numbers[..=upto] without overflowing, and what it came to, and
+    // says whether to keep looking for more.
+    fn grow(&self, operators: &[Operand], upto: usize, value: u64, prefix: &mut Vec<Operand>, finish: &mut impl FnMut(&[Operand], u64) -> bool) -> bool {
+        if prefix.len() == upto {
+            return finish(prefix, value);
+        }
+        for operator in operators {
+            if let Some(next) = operator.apply(value, self.numbers[prefix.len() + 1]) {
+                prefix.push(*operator);
+                let more = self.grow(operators, upto, next, prefix, finish);
+                prefix.pop();
+                if !more {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn search(&self, operators: &[Operand], all: bool) -> Vec<Vec<Operand>> {
+        let mut found = Vec::new();
+        if !self.numbers.is_empty() {
+            self.peel(operators, self.numbers.len() - 1, self.result, &mut Vec::new(), &mut found, all);
+        }
+        found
+    }
+
+    /// The operators to put between the numbers to get the result, if any.
+    pub fn solve(&self, operators: &[Operand]) -> Option<Vec<Operand>> {
+        self.search(operators, false).pop()
+    }
+}
+
+// The puzzle only asks whether there's a way, only the tests ask for every way
+// or to see the working.
+#[allow(dead_code)]
+impl Calibration {
+
+    /// Every way there is of getting the result. Custom operators get every
+    /// left side tried going forwards, not just the one their undo gives.
+    pub fn solve_all(&self, operators: &[Operand]) -> Vec<Vec<Operand>> {
+        self.search(operators, true)
+    }
+
+    /// Left to right, None if it overflows or an operator can't be applied.
+    pub fn evaluate(&self, chain: &[Operand]) -> Option<u64> {
+        let (first, rest) = self.numbers.split_first()?;
+        if rest.len() != chain.len() {
+            return None;
+        }
+        rest.iter().zip(chain).try_fold(*first, |left, (right, operator)| operator.apply(left, *right))
+    }
+
+    /// Like "81 * 40 + 27 = 3267".
+    pub fn show(&self, chain: &[Operand]) -> String {
+        let mut shown = self.numbers.first().map(|n| n.to_string()).unwrap_or_default();
+        for (operator, number) in chain.iter().zip(&self.numbers[1..]) {
+            shown.push_str(&format!(" {} {}", operator.symbol(), number));
+        }
+        format!("{} = {}", shown, self.result)
+    }
+}
+
+/// An operator that isn't one of the puzzle's. `undo(result, right)` is what
+/// the left side must have been for `apply(left, right)` to give result, or
+/// None if nothing works. There's room for one answer only, so solve only
+/// tries that one, if several left sides would work it can miss a way.
+/// solve_all doesn't use undo and finds them all.
+#[derive(Debug, Clone, Copy)]
+pub struct Custom {
+    pub symbol: &'static str,
+    pub apply: fn(u64, u64) -> Option<u64>,
+    pub undo: fn(u64, u64) -> Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Operand {
+    Plus,
+    Multiply,
+    Concat,
+    // Only the tests bring their own.
+    #[allow(dead_code)]
+    Other(Custom),
+}
+
+// What the left side of an operator had to be.
+enum Undone {
+    Nothing,
+    Left(u64),
+    /// Anything times 0 is 0, or a custom operator that can't be trusted to say.
+    AnyLeft,
+}
+
+fn digits_multiplier(number: u64) -> Option<u64> {
+    10u64.checked_pow(number.checked_ilog10().unwrap_or(0) + 1)
+}
+
+impl Operand {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operand::Plus => "+",
+            Operand::Multiply => "*",
+            Operand::Concat => "||",
+            Operand::Other(custom) => custom.symbol,
+        }
+    }
+
+    pub fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        match self {
+            Operand::Plus => left.checked_add(right),
+            Operand::Multiply => left.checked_mul(right),
+            Operand::Concat => left.checked_mul(digits_multiplier(right)?)?.checked_add(right),
+            Operand::Other(custom) => (custom.apply)(left, right),
+        }
+    }
+
+    fn undo(&self, result: u64, right: u64) -> Undone {
+        let left = match self {
+            Operand::Plus => result.checked_sub(right),
+            Operand::Multiply if right == 0 => return if result == 0 { Undone::AnyLeft } else { Undone::Nothing },
+            Operand::Multiply => result.is_multiple_of(right).then(|| result / right),
+            // A 20 digit right side can't be concatenated onto anything, same as apply says.
+            Operand::Concat => digits_multiplier(right)
+                .and_then(|multiplier| (result % multiplier == right).then(|| result / multiplier)),
+            Operand::Other(custom) => (custom.undo)(result, right),
+        };
+        left.map_or(Undone::Nothing, Undone::Left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "190: 10 19\n3267: 81 40 27\n83: 17 5\n156: 15 6\n7290: 6 8 6 15\n161011: 16 10 13\n192: 17 8 14\n21037: 9 7 18 13\n292: 11 6 16 20";
+
+    const ALL: [Operand; 3] = [Operand::Plus, Operand::Multiply, Operand::Concat];
+
+    // Every combination going forwards, how it used to be done.
+    fn brute_force(calibration: &Calibration, operators: &[Operand]) -> usize {
+        let gaps = calibration.numbers.len() - 1;
+        let mut ways = 0;
+        for combination in 0..operators.len().pow(gaps as u32) {
+            let chain: Vec<Operand> = (0..gaps)
+                .map(|gap| operators[combination / operators.len().pow(gap as u32) % operators.len()])
+                .collect();
+            if calibration.evaluate(&chain) == Some(calibration.result) {
+                ways += 1;
+            }
+        }
+        ways
+    }
+
+    fn total(operators: &[Operand]) -> u64 {
+        SAMPLE.lines().map(Calibration::from).filter(|c| c.solve(operators).is_some()).map(|c| c.result).sum()
+    }
+
+    #[test]
+    fn test_sample() {
+        assert_eq!(total(&ALL[..2]), 3749);
+        assert_eq!(total(&ALL), 11387);
+
+        let calibration = Calibration::from("7290: 6 8 6 15");
+        let chain = calibration.solve(&ALL).unwrap();
+        assert_eq!(calibration.show(&chain), "6 * 8 || 6 * 15 = 7290");
+        assert_eq!(calibration.evaluate(&chain), Some(7290));
+
+        let calibration = Calibration::from("292: 11 6 16 20");
+        let shown: Vec<String> = calibration.solve_all(&ALL).iter().map(|chain| calibration.show(chain)).collect();
+        assert_eq!(shown, vec!["11 + 6 * 16 + 20 = 292"]);
+    }
+
+    #[test]
+    fn test_agrees_with_brute_force() {
+        for line in SAMPLE.lines().chain(["6: 1 1 1 1 2", "100: 10 10 1 0 0", "1: 1 1 1 1 1 1", "11: 1 1 1", "5: 3 0 5"]) {
+            let calibration = Calibration::from(line);
+            let all = calibration.solve_all(&ALL);
+            assert_eq!(all.len(), brute_force(&calibration, &ALL), "{}", line);
+            for chain in &all {
+                assert_eq!(calibration.evaluate(chain), Some(calibration.result));
+            }
+        }
+    }
+
+    #[test]
+    fn test_zeros() {
+        // Times 0 makes whatever came before it not matter, as long as it
+        // didn't overflow getting there.
+        for line in [
+            "0: 5 0",
+            "0: 7 3 0",
+            "0: 0 0 0 0",
+            "5: 3 0 5",
+            "7: 4 9 0 7",
+            "10: 0 1 0",
+            "0: 18446744073709551615 10 0",
+            "18446744073709551615: 0 18446744073709551615",
+        ] {
+            let calibration = Calibration::from(line);
+            let all = calibration.solve_all(&ALL);
+            assert_eq!(all.len(), brute_force(&calibration, &ALL), "{}", line);
+            for chain in &all {
+                assert_eq!(calibration.evaluate(chain), Some(calibration.result), "{}", line);
+            }
+            assert_eq!(calibration.solve(&ALL).is_some(), !all.is_empty(), "{}", line);
+        }
+        let calibration = Calibration::from("5: 3 0 5");
+        assert_eq!(calibration.show(&calibration.solve(&ALL).unwrap()), "3 * 0 + 5 = 5");
+    }
+
+    #[test]
+    fn test_custom_operator() {
+        let minus = Operand::Other(Custom {
+            symbol: "-",
+            apply: u64::checked_sub,
+            undo: u64::checked_add,
+        });
+        let calibration = Calibration::from("3: 10 2 5");
+        assert!(calibration.solve(&ALL).is_none());
+        let chain = calibration.solve(&[Operand::Plus, minus]).unwrap();
+        assert_eq!(calibration.show(&chain), "10 - 2 - 5 = 3");
+        // Big enough that concat overflows instead of wrapping round.
+        let big = Calibration::from("18446744073709551615: 18446744073709551615 1");
+        assert_eq!(big.evaluate(&[Operand::Concat]), None);
+        assert!(big.solve(&ALL).is_some());
+    }
+
+    #[test]
+    fn test_custom_operator_with_several_left_sides() {
+        // 6, 7 and 8 all divide by 3 to 2, undo can only say 6.
+        let divide = Operand::Other(Custom {
+            symbol: "/",
+            apply: u64::checked_div,
+            undo: u64::checked_mul,
+        });
+        let calibration = Calibration::from("2: 7 3");
+        assert!(calibration.solve(&[divide]).is_none());
+        assert_eq!(calibration.solve_all(&[divide]).len(), 1);
+
+        let calibration = Calibration::from("3: 4 3 2");
+        let operators = [Operand::Plus, divide];
+        let shown: Vec<String> = calibration.solve_all(&operators).iter().map(|chain| calibration.show(chain)).collect();
+        assert_eq!(shown, vec!["4 / 3 + 2 = 3", "4 + 3 / 2 = 3"]);
+        assert_eq!(shown.len(), brute_force(&calibration, &operators));
+    }
+}
@@ -0,0 +1,210 @@
+/* Antinodes as points on the line through two antennas.
+ *
+ * The line through (0,0) and (2,4) goes through (1,2) too, so stepping along
+ * it by the raw delta skips half the points. Dividing the delta by the gcd of
+ * its parts gives the smallest step that still lands on whole numbers, and
+ * every lattice point on the line is a whole number of those steps from either
+ * antenna. Which of those steps are inside the map can be worked out per axis
+ * with a floor and a ceiling instead of walking until we fall off the edge.
+ */
+
+use std::collections::{BTreeMap, HashSet};
+
+/// (row, col)
+pub type Point = (i64, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub rows: i64,
+    pub cols: i64,
+}
+
+impl Bounds {
+    pub fn contains(&self, (row, col): Point) -> bool {
+        row >= 0 && col >= 0 && row < self.rows && col < self.cols
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AntinodeMode {
+    /// Part 1: beyond each antenna, as far again as the other one is.
+    Doubled,
+    /// Part 2: every point in line with both of them.
+    Harmonic,
+    /// Every point in line where one antenna is exactly `far / near` times as
+    /// far away as the other, in between them too. Ratio(2, 1) is Doubled plus
+    /// the points a third of the way along, if they land on whole numbers.
+    // The puzzle only asks for Doubled and Harmonic.
+    #[allow(dead_code)]
+    Ratio { far: i64, near: i64 },
+}
+
+#[derive(Debug, Default)]
+pub struct Antinodes {
+    pub by_frequency: BTreeMap<char, HashSet<Point>>,
+    pub all: HashSet<Point>,
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+// Division rounding towards negative infinity, / rounds towards zero.
+fn floor_div(n: i64, d: i64) -> i64 {
+    let q = n / d;
+    if n % d != 0 && ((n < 0) != (d < 0)) { q - 1 } else { q }
+}
+
+fn ceil_div(n: i64, d: i64) -> i64 {
+    -floor_div(-n, d)
+}
+
+/// Every whole number point on the line through a and b that's inside the
+/// bounds, in order going from a towards b. If a and b are the same point
+/// there's no line, just the point.
+pub fn lattice_line(a: Point, b: Point, bounds: Bounds) -> Vec<Point> {
+    if a == b {
+        return if bounds.contains(a) { vec![a] } else { Vec::new() };
+    }
+    let divisor = gcd(b.0 - a.0, b.1 - a.1);
+    let step = ((b.0 - a.0) / divisor, (b.1 - a.1) / divisor);
+
+    // a + k * step is inside when low <= a + k * step <= high on both axes.
+    let mut k_range = (i64::MIN, i64::MAX);
+    for (start, step, high) in [(a.0, step.0, bounds.rows - 1), (a.1, step.1, bounds.cols - 1)] {
+        let (low_k, high_k) = match step {
+            0 if (0..=high).contains(&start) => continue,
+            0 => return Vec::new(),
+            s if s > 0 => (ceil_div(-start, s), floor_div(high - start, s)),
+            s => (ceil_div(high - start, s), floor_div(-start, s)),
+        };
+        k_range = (k_range.0.max(low_k), k_range.1.min(high_k));
+    }
+    (k_range.0..=k_range.1).map(|k| (a.0 + k * step.0, a.1 + k * step.1)).collect()
+}
+
+// Both coordinates of (numerator / denominator), if they're whole numbers.
+fn exact(numerator: Point, denominator: i64) -> Option<Point> {
+    if denominator == 0 || numerator.0 % denominator != 0 || numerator.1 % denominator != 0 {
+        return None;
+    }
+    Some((numerator.0 / denominator, numerator.1 / denominator))
+}
+
+/// The antinodes one pair of antennas makes, sorted.
+pub fn pair_antinodes(a: Point, b: Point, bounds: Bounds, mode: AntinodeMode) -> Vec<Point> {
+    let found = match mode {
+        AntinodeMode::Harmonic => return lattice_line(a, b, bounds),
+        AntinodeMode::Doubled => vec![(2 * a.0 - b.0, 2 * a.1 - b.1), (2 * b.0 - a.0, 2 * b.1 - a.1)],
+        AntinodeMode::Ratio { far, near } if far <= 0 || near <= 0 => Vec::new(),
+        AntinodeMode::Ratio { far, near } => {
+            // p with |p - b| = r * |p - a| is (r a - b) / (r - 1) past a, or
+            // (r a + b) / (r + 1) between them, and the same again the other way.
+            let mut found = Vec::new();
+            for (x, y) in [(a, b), (b, a)] {
+                let outside = (far * x.0 - near * y.0, far * x.1 - near * y.1);
+                let between = (far * x.0 + near * y.0, far * x.1 + near * y.1);
+                found.extend(exact(outside, far - near));
+                found.extend(exact(between, far + near));
+            }
+            found
+        }
+    };
+    let mut found: Vec<Point> = found.into_iter().filter(|&p| bounds.contains(p) && a != b).collect();
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Antinodes from every pair of antennas on the same frequency.
+pub fn antinodes(antennas: &BTreeMap<char, Vec<Point>>, bounds: Bounds, mode: AntinodeMode) -> Antinodes {
+    let mut found = Antinodes::default();
+    for (&frequency, locations) in antennas {
+        let mut these = HashSet::new();
+        for (i, &a) in locations.iter().enumerate() {
+            for &b in &locations[i + 1..] {
+                these.extend(pair_antinodes(a, b, bounds, mode));
+            }
+        }
+        found.all.extend(&these);
+        found.by_frequency.insert(frequency, these);
+    }
+    found
+}
+
+/// Every antenna by frequency, and how big the map is.
+pub fn parse_map(raw_data: &str) -> (BTreeMap<char, Vec<Point>>, Bounds) {
+    let lines: Vec<&str> = raw_data.lines().filter(|line| !line.is_empty()).collect();
+    let bounds = Bounds {
+        rows: lines.len() as i64,
+        cols: lines.first().map_or(0, |line| line.chars().count() as i64),
+    };
+    let mut antennas: BTreeMap<char, Vec<Point>> = BTreeMap::new();
+    for (row, line) in lines.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            if c != '.' {
+                antennas.entry(c).or_default().push((row as i64, col as i64));
+            }
+        }
+    }
+    (antennas, bounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "............\n........0...\n.....0......\n.......0....\n....0.......\n......A.....\n............\n............\n........A...\n.........A..\n............\n............";
+
+    #[test]
+    fn test_example() {
+        let (antennas, bounds) = parse_map(EXAMPLE);
+        assert_eq!(antinodes(&antennas, bounds, AntinodeMode::Doubled).all.len(), 14);
+        let harmonic = antinodes(&antennas, bounds, AntinodeMode::Harmonic);
+        assert_eq!(harmonic.all.len(), 34);
+        assert_eq!(harmonic.by_frequency.keys().copied().collect::<Vec<_>>(), vec!['0', 'A']);
+
+        let (t_antennas, t_bounds) = parse_map("T.........\n...T......\n.T........\n..........\n..........\n..........\n..........\n..........\n..........\n..........");
+        assert_eq!(antinodes(&t_antennas, t_bounds, AntinodeMode::Harmonic).all.len(), 9);
+    }
+
+    #[test]
+    fn test_lattice_line() {
+        let bounds = Bounds { rows: 7, cols: 7 };
+        assert_eq!(lattice_line((0, 0), (2, 4), bounds), vec![(0, 0), (1, 2), (2, 4), (3, 6)]);
+        assert_eq!(lattice_line((4, 2), (2, 0), bounds), vec![(6, 4), (5, 3), (4, 2), (3, 1), (2, 0)]);
+        assert_eq!(lattice_line((3, 5), (3, 1), bounds).len(), 7);
+        assert_eq!(lattice_line((-2, 0), (-2, 3), bounds), vec![]);
+        assert_eq!(lattice_line((1, 1), (1, 1), bounds), vec![(1, 1)]);
+
+        // Against walking the whole grid and checking the cross product.
+        for a in [(0, 0), (3, 2), (6, 1), (-3, 9)] {
+            for b in [(1, 3), (5, 5), (2, -4), (6, 1)] {
+                if a == b {
+                    continue;
+                }
+                let mut expected: Vec<Point> = (0..7)
+                    .flat_map(|r| (0..7).map(move |c| (r, c)))
+                    .filter(|p| (b.0 - a.0) * (p.1 - a.1) == (b.1 - a.1) * (p.0 - a.0))
+                    .collect();
+                let mut line = lattice_line(a, b, bounds);
+                expected.sort();
+                line.sort();
+                assert_eq!(line, expected, "{:?} {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ratios() {
+        let bounds = Bounds { rows: 20, cols: 20 };
+        // (0, -3) is off the map.
+        assert_eq!(pair_antinodes((3, 3), (6, 9), bounds, AntinodeMode::Doubled), vec![(9, 15)]);
+        // The delta is divisible by 3, so the points a third of the way count too.
+        let ratio = pair_antinodes((3, 3), (6, 9), bounds, AntinodeMode::Ratio { far: 2, near: 1 });
+        assert_eq!(ratio, vec![(4, 5), (5, 7), (9, 15)]);
+        // 1 to 1 is just the middle.
+        assert_eq!(pair_antinodes((2, 2), (4, 6), bounds, AntinodeMode::Ratio { far: 1, near: 1 }), vec![(3, 4)]);
+        assert_eq!(pair_antinodes((2, 2), (4, 6), bounds, AntinodeMode::Ratio { far: 0, near: 1 }), vec![]);
+    }
+}
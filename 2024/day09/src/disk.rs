@@ -0,0 +1,301 @@
+/* The disk as spans of blocks instead of a queue of segments.
+ *
+ * Moving whole files used to mean scanning from the front for the first gap
+ * big enough, for every file. Files are at most 9 blocks long, so keep a
+ * min-heap of gap starts for each length 1 to 9, anything longer can fit any
+ * file and goes in with the 9s. The leftmost gap that fits a file is then the
+ * smallest start out of the tops of the heaps for lengths >= the file's, 9
+ * peeks at most. Whatever's left of the gap goes back into the heap for its
+ * new length.
+ *
+ * Gaps can be longer than 9 when a file is 0 blocks long, the free space
+ * either side of it is one gap.
+ *
+ * The space a file leaves behind is never worth remembering: files move from
+ * the right end down and never to the right, so everything still to move is
+ * to the left of it.
+ */
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+
+const LONGEST_SPAN: usize = 9;
+
+#[derive(Debug, PartialEq)]
+pub enum DiskError {
+    NotADigit { position: usize, found: char },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    start: u64,
+    len: u64,
+}
+
+/// Some or all of a file's blocks, sitting together on the disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Piece {
+    pub id: u64,
+    pub start: u64,
+    pub len: u64,
+}
+
+#[derive(Debug)]
+pub struct DiskMap {
+    // Indexed by file id.
+    files: Vec<Span>,
+    // In order along the disk, the empty ones left out.
+    free: Vec<Span>,
+    size: u64,
+}
+
+/// Where everything ended up after compacting.
+#[derive(Debug, PartialEq)]
+pub struct Layout {
+    /// In order along the disk, anything between them is free.
+    pub pieces: Vec<Piece>,
+    pub size: u64,
+}
+
+impl TryFrom<&str> for DiskMap {
+    type Error = DiskError;
+
+    fn try_from(raw_data: &str) -> Result<DiskMap, DiskError> {
+        let mut files = Vec::new();
+        let mut free: Vec<Span> = Vec::new();
+        let mut at = 0;
+        let digits = raw_data.chars().enumerate().filter(|(_, c)| !c.is_whitespace());
+        for (i, (position, c)) in digits.enumerate() {
+            let len = c.to_digit(10).ok_or(DiskError::NotADigit { position, found: c })? as u64;
+            let span = Span { start: at, len };
+            match free.last_mut() {
+                _ if i % 2 == 0 => files.push(span),
+                _ if len == 0 => {}
+                Some(gap) if gap.start + gap.len == at => gap.len += len,
+                _ => free.push(span),
+            }
+            at += len;
+        }
+        Ok(DiskMap { files, free, size: at })
+    }
+}
+
+impl DiskMap {
+    /// Part 1: move blocks one at a time from the end of the disk into the
+    /// first free block, splitting files up wherever they need to go.
+    pub fn compact_blocks(&self) -> Layout {
+        let mut free: VecDeque<Span> = self.free.iter().copied().collect();
+        let mut pieces = Vec::with_capacity(self.files.len() * 2);
+        for (id, file) in self.files.iter().enumerate().rev() {
+            let mut remaining = file.len;
+            while remaining > 0 {
+                let Some(gap) = free.front_mut().filter(|gap| gap.start < file.start) else {
+                    break;
+                };
+                let moved = gap.len.min(remaining);
+                pieces.push(Piece { id: id as u64, start: gap.start, len: moved });
+                gap.start += moved;
+                gap.len -= moved;
+                if gap.len == 0 {
+                    free.pop_front();
+                }
+                remaining -= moved;
+            }
+            // Blocks come off the end of the file, so what's left is the front of it.
+            if remaining > 0 {
+                pieces.push(Piece { id: id as u64, start: file.start, len: remaining });
+            }
+        }
+        Layout::of(pieces, self.size)
+    }
+
+    /// Part 2: move each whole file, highest id first, into the leftmost gap
+    /// it fits in, if that's to the left of where it is now.
+    pub fn compact_files(&self) -> Layout {
+        // gaps[len] holds every gap that's len blocks long, or longer for 9,
+        // smallest start on top.
+        let mut gaps: Vec<BinaryHeap<Reverse<(u64, u64)>>> = vec![BinaryHeap::new(); LONGEST_SPAN + 1];
+        let bucket = |len: u64| (len as usize).min(LONGEST_SPAN);
+        for gap in &self.free {
+            gaps[bucket(gap.len)].push(Reverse((gap.start, gap.len)));
+        }
+
+        let mut pieces = Vec::with_capacity(self.files.len());
+        for (id, file) in self.files.iter().enumerate().rev() {
+            // A file with no blocks would happily take any gap and waste it.
+            if file.len == 0 {
+                continue;
+            }
+            let leftmost = (file.len as usize..=LONGEST_SPAN)
+                .filter_map(|len| gaps[len].peek().map(|&Reverse(gap)| gap))
+                .filter(|&(start, _)| start < file.start)
+                .min();
+            let start = match leftmost {
+                None => file.start,
+                Some((start, len)) => {
+                    gaps[bucket(len)].pop();
+                    let left_over = len - file.len;
+                    if left_over > 0 {
+                        gaps[bucket(left_over)].push(Reverse((start + file.len, left_over)));
+                    }
+                    start
+                }
+            };
+            pieces.push(Piece { id: id as u64, start, len: file.len });
+        }
+        Layout::of(pieces, self.size)
+    }
+}
+
+impl Layout {
+    fn of(mut pieces: Vec<Piece>, size: u64) -> Layout {
+        pieces.retain(|piece| piece.len > 0);
+        pieces.sort_by_key(|piece| piece.start);
+        Layout { pieces, size }
+    }
+
+    /// Each block's position times the id of the file in it, added up.
+    pub fn checksum(&self) -> u64 {
+        self.pieces
+            .iter()
+            .map(|piece| piece.id * (piece.len * piece.start + piece.len * (piece.len - 1) / 2))
+            .sum()
+    }
+}
+
+/* Runs of the same thing as what x how many, "." for free blocks, so the
+ * example's 0099811188827773336446555566.............. comes out as
+ *
+ *   0x2 9x2 8x1 1x3 8x3 2x1 7x3 3x3 6x1 4x2 6x1 5x4 6x2 .x14
+ *
+ * and file 10 twice can't be mistaken for file 1, then file 0, then 1 and 0.
+ */
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut runs: Vec<(Option<u64>, u64)> = Vec::new();
+        let mut push = |what: Option<u64>, len: u64| match runs.last_mut() {
+            _ if len == 0 => {}
+            Some((last, last_len)) if *last == what => *last_len += len,
+            _ => runs.push((what, len)),
+        };
+        let mut at = 0;
+        for piece in &self.pieces {
+            push(None, piece.start - at);
+            push(Some(piece.id), piece.len);
+            at = piece.start + piece.len;
+        }
+        push(None, self.size - at);
+
+        let shown: Vec<String> = runs
+            .iter()
+            .map(|(what, len)| match what {
+                None => format!(".x{}", len),
+                Some(id) => format!("{}x{}", id, len),
+            })
+            .collect();
+        write!(f, "{}", shown.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "2333133121414131402\n";
+
+    #[test]
+    fn test_example() {
+        let disk = DiskMap::try_from(EXAMPLE).unwrap();
+        let blocks = disk.compact_blocks();
+        assert_eq!(blocks.checksum(), 1928);
+        assert_eq!(blocks.to_string(), "0x2 9x2 8x1 1x3 8x3 2x1 7x3 3x3 6x1 4x2 6x1 5x4 6x2 .x14");
+        let files = disk.compact_files();
+        assert_eq!(files.checksum(), 2858);
+        assert_eq!(files.to_string(), "0x2 9x2 2x1 1x3 7x3 .x1 4x2 .x1 3x3 .x4 5x4 .x1 6x4 .x5 8x4 .x2");
+    }
+
+    #[test]
+    fn test_ids_past_nine() {
+        // Twelve files, one block each with one free block after.
+        let disk = DiskMap::try_from("11".repeat(12).as_str()).unwrap();
+        let layout = disk.compact_files();
+        assert_eq!(layout.to_string(), "0x1 11x1 1x1 10x1 2x1 9x1 3x1 8x1 4x1 7x1 5x1 6x1 .x12");
+        assert_eq!(layout.pieces[1], Piece { id: 11, start: 1, len: 1 });
+    }
+
+    #[test]
+    fn test_empty_files() {
+        // File 1 has no blocks, so 3 and 4 free blocks either side of it make
+        // one gap of 7, file 3 goes first and file 2 still fits after it.
+        let disk = DiskMap::try_from("1304611").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x1 3x1 2x6 .x8");
+        assert_eq!(disk.compact_files().checksum(), 3 + 2 * (2 + 3 + 4 + 5 + 6 + 7));
+    }
+
+    #[test]
+    fn test_early_gap_beats_exact_fit() {
+        // File 2 is three blocks and there's a gap of exactly three right before
+        // it, but the leftmost gap that fits is the nine after file 0.
+        let disk = DiskMap::try_from("19233312").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x1 3x1 2x3 1x2 .x17");
+        assert_eq!(disk.compact_files().checksum(), 32);
+    }
+
+    #[test]
+    fn test_gap_shrinks_and_is_used_again() {
+        // The gap of nine takes every file, getting one smaller each time.
+        let disk = DiskMap::try_from("1912111111141").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x1 6x1 5x1 4x1 3x1 2x1 1x1 .x18");
+    }
+
+    #[test]
+    fn test_gaps_of_every_size() {
+        let disk = DiskMap::try_from("1112131415161718191").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x1 9x1 1x1 8x1 7x1 2x1 6x1 5x1 4x1 3x1 .x45");
+        assert_eq!(disk.compact_files().checksum(), 203);
+    }
+
+    #[test]
+    fn test_nowhere_to_go() {
+        // Every file is nine long and no gap is, so only the blocks move.
+        let disk = DiskMap::try_from("9192939495969798").unwrap();
+        assert_eq!(disk.compact_blocks().to_string(), "0x9 7x1 1x9 7x2 2x9 7x3 3x9 7x3 6x1 4x9 6x5 5x9 6x3 .x36");
+        assert_eq!(disk.compact_blocks().checksum(), 11385);
+        assert_eq!(
+            disk.compact_files().to_string(),
+            "0x9 .x1 1x9 .x2 2x9 .x3 3x9 .x4 4x9 .x5 5x9 .x6 6x9 .x7 7x9 .x8"
+        );
+        assert_eq!(disk.compact_files().checksum(), 16506);
+        // No free space at all, and free space that's all zero long.
+        let disk = DiskMap::try_from("90909090909").unwrap();
+        assert_eq!(disk.compact_blocks().to_string(), "0x9 1x9 2x9 3x9 4x9 5x9");
+        assert_eq!(disk.compact_files().to_string(), "0x9 1x9 2x9 3x9 4x9 5x9");
+        let disk = DiskMap::try_from("10203040506").unwrap();
+        assert_eq!(disk.compact_blocks().to_string(), "0x1 1x2 2x3 3x4 4x5 5x6");
+        assert_eq!(disk.compact_files().to_string(), "0x1 1x2 2x3 3x4 4x5 5x6");
+    }
+
+    #[test]
+    fn test_gaps_merging_past_nine() {
+        // Empty files between gaps of nine make one gap of 54.
+        let disk = DiskMap::try_from("1909090909092").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x1 6x2 .x54");
+        assert_eq!(disk.compact_files().checksum(), 18);
+        let disk = DiskMap::try_from("2505050501104").unwrap();
+        assert_eq!(disk.compact_files().to_string(), "0x2 6x4 5x1 .x21");
+        assert_eq!(disk.compact_blocks().to_string(), "0x2 6x4 5x1 .x21");
+    }
+
+    #[test]
+    fn test_empty_disk() {
+        let disk = DiskMap::try_from("0").unwrap();
+        assert_eq!(disk.compact_blocks().to_string(), "");
+        assert_eq!(disk.compact_files().checksum(), 0);
+    }
+
+    #[test]
+    fn test_bad_input() {
+        assert_eq!(DiskMap::try_from("12a4").unwrap_err(), DiskError::NotADigit { position: 2, found: 'a' });
+    }
+}
@@ -0,0 +1,225 @@
+/* Every trail from every cell in one go, instead of walking them all again
+ * from each trailhead.
+ *
+ * Trails only ever go uphill, so a cell's trails are just its uphill
+ * neighbours' trails with one more step on the front. Go through the cells
+ * highest first and by the time we get to a cell all its neighbours are done:
+ *
+ *  - the summits it can reach are all its neighbours' summits together, kept
+ *    as a bitset with one bit per summit so joining them is just |
+ *  - the number of different trails is the sum of its neighbours' counts
+ *
+ * A summit reaches itself and has one trail, the one that's already there.
+ * Only one level's bitsets are needed at a time, the level above, so the rest
+ * get dropped as we go.
+ */
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepRule {
+    /// How much higher each step has to be.
+    pub step: u8,
+    /// Where trails start.
+    pub lowest: u8,
+    /// Where trails end.
+    pub highest: u8,
+}
+
+impl StepRule {
+    /// Up exactly 1 at a time, from 0 to 9.
+    pub fn standard() -> StepRule {
+        StepRule { step: 1, lowest: 0, highest: 9 }
+    }
+
+    // The puzzle only ever uses the standard rule, only the tests try others.
+    #[allow(dead_code)]
+    pub fn with_step(self, step: u8) -> StepRule {
+        StepRule { step, ..self }
+    }
+
+    #[allow(dead_code)]
+    pub fn with_heights(self, lowest: u8, highest: u8) -> StepRule {
+        StepRule { lowest, highest, ..self }
+    }
+
+    // The heights a trail can go through, highest first. Nothing at all if a
+    // trail can't go from lowest to highest in whole steps.
+    fn levels(&self) -> Vec<u8> {
+        if self.step == 0 || self.highest < self.lowest || !(self.highest - self.lowest).is_multiple_of(self.step) {
+            return Vec::new();
+        }
+        (self.lowest..=self.highest).rev().step_by(self.step as usize).collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Trails {
+    /// How many different summits each cell can get to, 0 for cells no trail
+    /// goes through.
+    pub scores: Vec<Vec<usize>>,
+    /// How many different trails there are from each cell up to a summit.
+    pub ratings: Vec<Vec<u64>>,
+    /// (row, col) of each cell at the lowest height, top to bottom.
+    pub trailheads: Vec<(usize, usize)>,
+}
+
+impl Trails {
+    pub fn total_score(&self) -> usize {
+        self.trailheads.iter().map(|&(row, col)| self.scores[row][col]).sum()
+    }
+
+    pub fn total_rating(&self) -> u64 {
+        self.trailheads.iter().map(|&(row, col)| self.ratings[row][col]).sum()
+    }
+}
+
+type Bitset = Vec<u64>;
+
+fn neighbours(row: usize, col: usize, rows: usize, cols: usize) -> impl Iterator<Item = (usize, usize)> {
+    [(0, 1), (1, 0), (0, -1), (-1, 0)].into_iter().filter_map(move |(dr, dc): (isize, isize)| {
+        let r = row.checked_add_signed(dr)?;
+        let c = col.checked_add_signed(dc)?;
+        (r < rows && c < cols).then_some((r, c))
+    })
+}
+
+pub fn find_trails(grid: &[Vec<u8>], rule: StepRule) -> Trails {
+    let rows = grid.len();
+    let cols = grid.first().map_or(0, |row| row.len());
+    let mut scores = vec![vec![0; cols]; rows];
+    let mut ratings = vec![vec![0; cols]; rows];
+
+    // Cells bucketed by height, so going through the levels is in height order.
+    let mut by_height: Vec<Vec<(usize, usize)>> = vec![Vec::new(); u8::MAX as usize + 1];
+    for (row, line) in grid.iter().enumerate() {
+        for (col, &height) in line.iter().enumerate() {
+            by_height[height as usize].push((row, col));
+        }
+    }
+    let summits = &by_height[rule.highest as usize];
+    let words = summits.len().div_ceil(64);
+
+    let mut reach: Vec<Vec<Bitset>> = vec![vec![Bitset::new(); cols]; rows];
+    let mut above: Option<u8> = None;
+    for height in rule.levels() {
+        for &(row, col) in &by_height[height as usize] {
+            let mut reaches = vec![0u64; words];
+            let mut trails = 0;
+            match above {
+                None => {
+                    let summit = summits.binary_search(&(row, col)).unwrap();
+                    reaches[summit / 64] |= 1 << (summit % 64);
+                    trails = 1;
+                }
+                Some(above) => {
+                    for (r, c) in neighbours(row, col, rows, cols).filter(|&(r, c)| grid[r][c] == above) {
+                        for (word, theirs) in reaches.iter_mut().zip(&reach[r][c]) {
+                            *word |= theirs;
+                        }
+                        trails += ratings[r][c];
+                    }
+                }
+            }
+            scores[row][col] = reaches.iter().map(|word| word.count_ones() as usize).sum();
+            ratings[row][col] = trails;
+            reach[row][col] = reaches;
+        }
+        if let Some(above) = above {
+            for &(row, col) in &by_height[above as usize] {
+                reach[row][col] = Bitset::new();
+            }
+        }
+        above = Some(height);
+    }
+
+    let trailheads = if rule.levels().is_empty() { Vec::new() } else { by_height[rule.lowest as usize].clone() };
+    Trails { scores, ratings, trailheads }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "89010123\n78121874\n87430965\n96549874\n45678903\n32019012\n01329801\n10456732";
+
+    fn grid(raw: &str) -> Vec<Vec<u8>> {
+        raw.lines().map(|line| line.bytes().map(|b| b - b'0').collect()).collect()
+    }
+
+    #[test]
+    fn test_example() {
+        let trails = find_trails(&grid(EXAMPLE), StepRule::standard());
+        assert_eq!(trails.total_score(), 36);
+        assert_eq!(trails.total_rating(), 81);
+        assert_eq!(trails.trailheads.len(), 9);
+        assert_eq!(trails.scores[0][2], 5);
+        assert_eq!(trails.ratings[0][2], 20);
+        // The top left 8 only gets to one 9, and there's only one way there.
+        assert_eq!((trails.scores[0][0], trails.ratings[0][0]), (1, 1));
+    }
+
+    #[test]
+    fn test_other_rules() {
+        // Up 2 at a time from 1 to 7, and standard finds nothing in the same map.
+        let map = grid("1357\n3000\n5000\n7531");
+        let odd = StepRule::standard().with_step(2).with_heights(1, 7);
+        let trails = find_trails(&map, odd);
+        assert_eq!(trails.trailheads, vec![(0, 0), (3, 3)]);
+        assert_eq!(trails.scores[0][0], 2);
+        assert_eq!(trails.ratings[0][0], 2);
+        assert_eq!(trails.total_score(), 3);
+        assert_eq!(find_trails(&map, StepRule::standard()).total_rating(), 0);
+
+        // 0 to 9 can't be done 2 at a time.
+        let uneven = StepRule::standard().with_step(2);
+        assert_eq!(find_trails(&grid(EXAMPLE), uneven).total_score(), 0);
+        assert_eq!(find_trails(&grid(EXAMPLE), StepRule::standard().with_step(0)).trailheads, vec![]);
+    }
+
+    #[test]
+    fn test_more_than_64_summits() {
+        // The example tiled 4 by 4, so the bitsets take more than one word. Trails
+        // cross from one tile into the next, so it's more than 16 examples' worth.
+        let tiled: Vec<Vec<u8>> = (0..4).flat_map(|_| grid(EXAMPLE).into_iter().map(|line| line.repeat(4))).collect();
+        assert_eq!(tiled.iter().flatten().filter(|&&height| height == 9).count(), 112);
+        let trails = find_trails(&tiled, StepRule::standard());
+        assert_eq!(trails.trailheads.len(), 144);
+        assert_eq!((trails.total_score(), trails.total_rating()), (882, 2100));
+        assert_eq!((trails.scores[0][2], trails.ratings[0][2]), (5, 20));
+    }
+
+    #[test]
+    fn test_slope() {
+        // Every cell is on a trail, and from the corner it's right or down nine
+        // times to any of the ten 9s on the diagonal.
+        let slope: Vec<Vec<u8>> = (0..10).map(|r| (0..10).map(|c| (r + c).min(9)).collect()).collect();
+        let trails = find_trails(&slope, StepRule::standard());
+        assert_eq!(trails.trailheads, vec![(0, 0)]);
+        assert_eq!((trails.scores[0][0], trails.ratings[0][0]), (10, 512));
+        let middle = find_trails(&slope, StepRule::standard().with_heights(2, 6));
+        assert_eq!(middle.trailheads.len(), 3);
+        assert_eq!((middle.total_score(), middle.total_rating()), (15, 48));
+    }
+
+    #[test]
+    fn test_steps_of_three() {
+        let map = grid("0369\n3690\n6903\n9036");
+        assert_eq!(find_trails(&map, StepRule::standard()).total_score(), 0);
+        let trails = find_trails(&map, StepRule::standard().with_step(3));
+        assert_eq!(trails.trailheads.len(), 4);
+        // Only the corner 0 gets anywhere, the others run into 3s going back down.
+        assert_eq!((trails.scores[0][0], trails.ratings[0][0]), (4, 8));
+        assert_eq!((trails.total_score(), trails.total_rating()), (4, 8));
+    }
+
+    #[test]
+    fn test_tiny_maps() {
+        let line = find_trails(&grid("0123456789"), StepRule::standard());
+        assert_eq!((line.total_score(), line.total_rating()), (1, 1));
+        assert_eq!(line.scores[0][5], 1);
+        let short = find_trails(&grid("0123456789"), StepRule::standard().with_heights(2, 6));
+        assert_eq!(short.trailheads, vec![(0, 2)]);
+        assert_eq!((short.scores[0][7], short.ratings[0][7]), (0, 0));
+        let nothing = find_trails(&grid("5"), StepRule::standard());
+        assert_eq!(nothing, Trails { scores: vec![vec![0]], ratings: vec![vec![0]], trailheads: vec![] });
+    }
+}
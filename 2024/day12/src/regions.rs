@@ -0,0 +1,362 @@
+/* Everything about the shape of a region comes out of looking at 2x2 windows.
+ *
+ * Put a window on every grid point a region's plots touch and count how many
+ * of the four plots around that point are in the region:
+ *
+ *   one in       X.   an outside corner
+ *                ..
+ *   three in     XX   an inside corner
+ *                X.
+ *   two diagonal X.   two corners at once, the fence pinches here
+ *                .X
+ *
+ * Every corner starts a new straight side, so sides is just the number of
+ * corners. The same counts give the Euler number (pieces minus holes) with
+ * Gray's bit quad formula, and since each region is one piece, holes = 1 - that.
+ * The diagonal windows are the only place 4 and 8 connectivity disagree: with
+ * 4 they're holes leaking out, with 8 they're the region joining up.
+ *
+ * https://en.wikipedia.org/wiki/Euler_characteristic (see digital images)
+ */
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+
+/// (row, col), can be negative since sparse sets can be anywhere.
+pub type Point = (i64, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Connectivity {
+    /// Up, down, left, right.
+    Four,
+    /// Diagonals too.
+    // The garden is always Four, only the tests ask for this one.
+    #[allow(dead_code)]
+    Eight,
+}
+
+impl Connectivity {
+    fn neighbours(&self) -> &'static [(i64, i64)] {
+        match self {
+            Connectivity::Four => &[(0, 1), (1, 0), (0, -1), (-1, 0)],
+            Connectivity::Eight => &[(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)],
+        }
+    }
+}
+
+/// Inclusive on both ends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub top: i64,
+    pub left: i64,
+    pub bottom: i64,
+    pub right: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region<L> {
+    pub label: L,
+    /// Sorted, so the first one is the top left most plot.
+    pub plots: Vec<Point>,
+    pub perimeter: u64,
+    pub sides: u64,
+    pub holes: u64,
+    pub bounds: Bounds,
+}
+
+impl<L> Region<L> {
+    pub fn area(&self) -> u64 {
+        self.plots.len() as u64
+    }
+
+    pub fn price(&self) -> u64 {
+        self.area() * self.perimeter
+    }
+
+    pub fn discounted_price(&self) -> u64 {
+        self.area() * self.sides
+    }
+}
+
+/// Every character in the grid as a labelled plot, blank lines skipped.
+pub fn plots_of_grid(raw_data: &str) -> HashMap<Point, char> {
+    raw_data
+        .lines()
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .flat_map(|(row, line)| line.chars().enumerate().map(move |(col, c)| ((row as i64, col as i64), c)))
+        .collect()
+}
+
+/// Split the plots up into connected regions of the same label and measure
+/// each one. Regions come back in order of their top left most plot.
+pub fn analyse<L: Copy + Eq + Hash>(plots: &HashMap<Point, L>, connectivity: Connectivity) -> Vec<Region<L>> {
+    let mut starts: Vec<Point> = plots.keys().copied().collect();
+    starts.sort_unstable();
+
+    let mut seen = HashSet::with_capacity(plots.len());
+    let mut regions = Vec::new();
+    for start in starts {
+        if !seen.insert(start) {
+            continue;
+        }
+        let label = plots[&start];
+        let mut members = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some((row, col)) = queue.pop_front() {
+            for (d_row, d_col) in connectivity.neighbours() {
+                let next = (row + d_row, col + d_col);
+                if plots.get(&next) == Some(&label) && seen.insert(next) {
+                    members.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        regions.push(measure(label, members, connectivity));
+    }
+    regions
+}
+
+fn measure<L>(label: L, mut plots: Vec<Point>, connectivity: Connectivity) -> Region<L> {
+    plots.sort_unstable();
+    let inside: HashSet<Point> = plots.iter().copied().collect();
+    let is_in = |point: Point| inside.contains(&point);
+
+    let mut perimeter = 0;
+    let mut bounds = Bounds { top: i64::MAX, left: i64::MAX, bottom: i64::MIN, right: i64::MIN };
+    let mut corners_of_plots = HashSet::with_capacity(plots.len() * 2);
+    for &(row, col) in &plots {
+        perimeter += Connectivity::Four.neighbours().iter().filter(|(dr, dc)| !is_in((row + dr, col + dc))).count() as u64;
+        bounds.top = bounds.top.min(row);
+        bounds.left = bounds.left.min(col);
+        bounds.bottom = bounds.bottom.max(row);
+        bounds.right = bounds.right.max(col);
+        // Grid point (r, c) is the top left corner of plot (r, c).
+        corners_of_plots.extend([(row, col), (row, col + 1), (row + 1, col), (row + 1, col + 1)]);
+    }
+
+    let (mut one_in, mut three_in, mut diagonal) = (0i64, 0i64, 0i64);
+    for (row, col) in corners_of_plots {
+        let window = [(row - 1, col - 1), (row - 1, col), (row, col - 1), (row, col)].map(is_in);
+        match window.iter().filter(|&&plot| plot).count() {
+            1 => one_in += 1,
+            3 => three_in += 1,
+            2 if window[0] == window[3] => diagonal += 1,
+            _ => {}
+        }
+    }
+
+    let euler_number = match connectivity {
+        Connectivity::Four => (one_in - three_in + 2 * diagonal) / 4,
+        Connectivity::Eight => (one_in - three_in - 2 * diagonal) / 4,
+    };
+
+    Region {
+        label,
+        plots,
+        perimeter,
+        sides: (one_in + three_in + 2 * diagonal) as u64,
+        holes: (1 - euler_number) as u64,
+        bounds,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LARGER_EXAMPLE: &str = "RRRRIICCFF\nRRRRIICCCF\nVVRRRCCFFF\nVVRCCCJFFF\nVVVVCJJCFE\nVVIVCCJJEE\nVVIIICJJEE\nMIIIIIJJEE\nMIIISIJEEE\nMMMISSJEEE";
+
+    fn totals(raw: &str) -> (u64, u64) {
+        let regions = analyse(&plots_of_grid(raw), Connectivity::Four);
+        (regions.iter().map(Region::price).sum(), regions.iter().map(Region::discounted_price).sum())
+    }
+
+    #[test]
+    fn test_examples() {
+        assert_eq!(totals("AAAA\nBBCD\nBBCC\nEEEC"), (140, 80));
+        assert_eq!(totals("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO"), (772, 436));
+        assert_eq!(totals("EEEEE\nEXXXX\nEEEEE\nEXXXX\nEEEEE"), (692, 236));
+        assert_eq!(totals("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA"), (1184, 368));
+        assert_eq!(totals(LARGER_EXAMPLE), (1930, 1206));
+    }
+
+    #[test]
+    fn test_holes_and_bounds() {
+        let regions = analyse(&plots_of_grid("OOOOO\nOXOXO\nOOOOO\nOXOXO\nOOOOO"), Connectivity::Four);
+        assert_eq!(regions[0].label, 'O');
+        assert_eq!(regions[0].holes, 4);
+        assert_eq!(regions[0].bounds, Bounds { top: 0, left: 0, bottom: 4, right: 4 });
+        assert_eq!(regions[1].holes, 0);
+        assert_eq!(regions[1].bounds, Bounds { top: 1, left: 1, bottom: 1, right: 1 });
+
+        // The hole leaks out through the diagonal with 4, but the X's join up with 8.
+        let ring = plots_of_grid("XXX.\nX..X\nXXX.");
+        let xs: HashMap<Point, char> = ring.into_iter().filter(|&(_, c)| c == 'X').collect();
+        let four = analyse(&xs, Connectivity::Four);
+        assert_eq!(four.len(), 2);
+        assert!(four.iter().all(|region| region.holes == 0));
+        let eight = analyse(&xs, Connectivity::Eight);
+        assert_eq!(eight.len(), 1);
+        assert_eq!(eight[0].holes, 1);
+        assert_eq!(eight[0].sides, 8 + 4);
+    }
+
+    #[test]
+    fn test_sparse() {
+        let far_apart: HashMap<Point, ()> =
+            [(-1_000_000, 5), (-1_000_000, 6), (-999_999, 5), (7, -3)].into_iter().map(|p| (p, ())).collect();
+        let regions = analyse(&far_apart, Connectivity::Four);
+        assert_eq!(regions.len(), 2);
+        assert_eq!((regions[0].area(), regions[0].perimeter, regions[0].sides), (3, 8, 6));
+        assert_eq!((regions[1].area(), regions[1].perimeter, regions[1].sides), (1, 4, 4));
+        assert_eq!(regions[1].bounds, Bounds { top: 7, left: -3, bottom: 7, right: -3 });
+    }
+
+    // (label, area, perimeter, sides, holes) for every region, in the order analyse gives them.
+    fn shapes(raw: &str, connectivity: Connectivity) -> Vec<(char, u64, u64, u64, u64)> {
+        analyse(&plots_of_grid(raw), connectivity)
+            .iter()
+            .map(|region| (region.label, region.area(), region.perimeter, region.sides, region.holes))
+            .collect()
+    }
+
+    #[test]
+    fn test_nested_rings() {
+        // Each ring is one hole round the next, whichever way the plots join up.
+        let rings = "AAAAAAA\nABBBBBA\nABAAABA\nABABABA\nABAAABA\nABBBBBA\nAAAAAAA";
+        let expected = vec![('A', 24, 48, 8, 1), ('B', 16, 32, 8, 1), ('A', 8, 16, 8, 1), ('B', 1, 4, 4, 0)];
+        assert_eq!(shapes(rings, Connectivity::Four), expected);
+        assert_eq!(shapes(rings, Connectivity::Eight), expected);
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        let board = "ABAB\nBABA\nABAB\nBABA";
+        let four = shapes(board, Connectivity::Four);
+        assert_eq!(four.len(), 16);
+        assert!(four.iter().all(|&(_, area, perimeter, sides, holes)| (area, perimeter, sides, holes) == (1, 4, 4, 0)));
+        // With 8 each letter is one piece, and the two middle plots of the other
+        // letter are walled in on all four sides. Every corner pinches, so no
+        // two fence pieces make one side.
+        assert_eq!(shapes(board, Connectivity::Eight), vec![('A', 8, 32, 32, 2), ('B', 8, 32, 32, 2)]);
+    }
+
+    #[test]
+    fn test_holes_touching_at_corners() {
+        // The B's make an X, one hole with 4 since it joins up diagonally, five with 8.
+        let cross = "AAAAA\nABABA\nAABAA\nABABA\nAAAAA";
+        let four = shapes(cross, Connectivity::Four);
+        assert_eq!(four[0], ('A', 20, 40, 24, 1));
+        assert_eq!(four[1..], [('B', 1, 4, 4, 0); 5]);
+        assert_eq!(shapes(cross, Connectivity::Eight), vec![('A', 20, 40, 24, 5), ('B', 5, 20, 20, 0)]);
+    }
+
+    /* The slow obvious way to get every number, to check the corners against. */
+
+    fn brute_force_perimeter(plots: &HashSet<Point>) -> u64 {
+        let mut perimeter = 0;
+        for &(r, c) in plots {
+            for (dr, dc) in Connectivity::Four.neighbours() {
+                if !plots.contains(&(r + dr, c + dc)) {
+                    perimeter += 1;
+                }
+            }
+        }
+        perimeter
+    }
+
+    // Every fence piece, grouped by which way it faces and which line it's on,
+    // then a new side wherever consecutive pieces on that line have a gap.
+    fn brute_force_sides(plots: &HashSet<Point>) -> u64 {
+        let mut lines: HashMap<(i64, i64, i64, bool), Vec<i64>> = HashMap::new();
+        for &(r, c) in plots {
+            for &(dr, dc) in Connectivity::Four.neighbours() {
+                if plots.contains(&(r + dr, c + dc)) {
+                    continue;
+                }
+                let horizontal = dr != 0;
+                let (line, along) = if horizontal { (r, c) } else { (c, r) };
+                lines.entry((dr, dc, line, horizontal)).or_default().push(along);
+            }
+        }
+        let mut sides = 0;
+        for pieces in lines.values_mut() {
+            pieces.sort();
+            sides += 1 + pieces.windows(2).filter(|pair| pair[1] != pair[0] + 1).count() as u64;
+        }
+        sides
+    }
+
+    // Flood the outside in from the edge of a box one bigger than the region,
+    // the outside moving the other way round to the region (4 <-> 8), then
+    // count what's left over in pieces.
+    fn brute_force_holes(plots: &HashSet<Point>, bounds: Bounds, connectivity: Connectivity) -> u64 {
+        let outside_moves = match connectivity {
+            Connectivity::Four => Connectivity::Eight,
+            Connectivity::Eight => Connectivity::Four,
+        };
+        let in_box = |(r, c): Point| r >= bounds.top - 1 && r <= bounds.bottom + 1 && c >= bounds.left - 1 && c <= bounds.right + 1;
+        let flood = |start: Point, seen: &mut HashSet<Point>| {
+            let mut queue = VecDeque::from([start]);
+            seen.insert(start);
+            while let Some((r, c)) = queue.pop_front() {
+                for (dr, dc) in outside_moves.neighbours() {
+                    let next = (r + dr, c + dc);
+                    if in_box(next) && !plots.contains(&next) && seen.insert(next) {
+                        queue.push_back(next);
+                    }
+                }
+            }
+        };
+        let mut seen = HashSet::new();
+        flood((bounds.top - 1, bounds.left - 1), &mut seen);
+        let mut holes = 0;
+        for r in bounds.top..=bounds.bottom {
+            for c in bounds.left..=bounds.right {
+                if !plots.contains(&(r, c)) && !seen.contains(&(r, c)) {
+                    holes += 1;
+                    flood((r, c), &mut seen);
+                }
+            }
+        }
+        holes
+    }
+
+    // xorshift, so the random grids are the same every run.
+    fn random_grid(seed: u64, rows: i64, cols: i64, labels: u64) -> HashMap<Point, u64> {
+        let mut state = seed;
+        let mut plots = HashMap::new();
+        for r in 0..rows {
+            for c in 0..cols {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                plots.insert((r, c), state % labels);
+            }
+        }
+        plots
+    }
+
+    #[test]
+    fn test_agrees_with_brute_force() {
+        for seed in 1..=120u64 {
+            for connectivity in [Connectivity::Four, Connectivity::Eight] {
+                let (rows, cols) = (1 + (seed % 11) as i64, 1 + (seed / 11 % 11) as i64);
+                let plots = random_grid(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15), rows, cols, 2 + seed % 4);
+                let regions = analyse(&plots, connectivity);
+                assert_eq!(regions.iter().map(Region::area).sum::<u64>(), plots.len() as u64);
+                for region in regions {
+                    let members: HashSet<Point> = region.plots.iter().copied().collect();
+                    assert_eq!(region.perimeter, brute_force_perimeter(&members));
+                    assert_eq!(region.sides, brute_force_sides(&members));
+                    assert_eq!(region.holes, brute_force_holes(&members, region.bounds, connectivity), "{:?}", region);
+                    assert!(region.plots.iter().all(|&(r, c)| r >= region.bounds.top
+                        && r <= region.bounds.bottom
+                        && c >= region.bounds.left
+                        && c <= region.bounds.right));
+                }
+            }
+        }
+    }
+}
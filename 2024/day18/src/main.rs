@@ -1,4 +1,10 @@
 pub mod boilerplate;
+// Shared with 2025 day08, which uses the other parts of it.
+#[allow(dead_code)]
+#[path = "../../../2025/day08/src/disjoint_set.rs"]
+mod disjoint_set;
+
+use disjoint_set::RollbackDisjointSet;
 
 use std::collections::{BinaryHeap, HashMap};
 use std::cmp::Reverse;
@@ -32,19 +38,69 @@ fn part_1(data: &str) {
 fn part_2(data: &str) {
     let rows = 71; // 7 for example, 70 for real
     let cols = 71; // 7 for example, 70 for real
-    let steps_to_simulate = 1024;
-    let graph = get_empty_matrix(rows, cols);
     let obstacles = get_obstacles(data);
-    for step_no in steps_to_simulate..obstacles.len() {
-        let corrupted_graph = place_obstacles_on_graph(&graph, &obstacles, step_no);
-        let start = (0,0);
-        let end = (70,70);
-        if dijkstra(&corrupted_graph, start, end).is_none() {
-            println!("step_no: {}", step_no);
-            break;
+    let start = (0,0);
+    let end = (70,70);
+    // Used to re-run dijkstra for every step after 1024, now it's one pass backwards.
+    match first_blocking_obstacle(rows, cols, &obstacles, start, end) {
+        Some(step_no) => {
+            let (row, col) = obstacles[step_no];
+            println!("step_no: {}", step_no + 1);
+            println!("Part 2 {},{}", col, row);
         }
+        None => println!("Part 2 the exit never gets cut off"),
     }
-    println!("Part 2 done I guess");
+}
+
+/* Offline connectivity: drop every obstacle, then pick them back up in reverse
+ * order joining each freed cell to its open neighbours. The first one whose
+ * removal connects start and end is the one that cut them apart going forward.
+ * Returns the index of that obstacle in the list.
+ *
+ * This is the job the rollback flavour is for. The replay only ever adds
+ * open cells, so nothing gets undone here, but without path compression
+ * asking whether start and end are together doesn't rewrite anything.
+ */
+fn first_blocking_obstacle(rows: usize, cols: usize, obstacles: &[Position], start: Position, end: Position) -> Option<usize> {
+    let cell = |(r, c): Position| r * cols + c;
+    // The same byte can fall in the same spot twice, it's only open once both are gone.
+    let mut blocked = vec![0usize; rows * cols];
+    for &obstacle in obstacles {
+        blocked[cell(obstacle)] += 1;
+    }
+
+    let mut open_areas = RollbackDisjointSet::new(rows * cols);
+    let join_neighbours = |open_areas: &mut RollbackDisjointSet, blocked: &[usize], (r, c): Position| {
+        let neighbours = [(r.wrapping_sub(1), c), (r + 1, c), (r, c.wrapping_sub(1)), (r, c + 1)];
+        for (nr, nc) in neighbours {
+            if nr < rows && nc < cols && blocked[cell((nr, nc))] == 0 {
+                open_areas.union(cell((r, c)), cell((nr, nc)));
+            }
+        }
+    };
+
+    for r in 0..rows {
+        for c in 0..cols {
+            if blocked[cell((r, c))] == 0 {
+                join_neighbours(&mut open_areas, &blocked, (r, c));
+            }
+        }
+    }
+    if blocked[cell(start)] == 0 && blocked[cell(end)] == 0 && open_areas.same(cell(start), cell(end)) {
+        return None;
+    }
+
+    for (step_no, &obstacle) in obstacles.iter().enumerate().rev() {
+        blocked[cell(obstacle)] -= 1;
+        if blocked[cell(obstacle)] > 0 {
+            continue;
+        }
+        join_neighbours(&mut open_areas, &blocked, obstacle);
+        if blocked[cell(start)] == 0 && blocked[cell(end)] == 0 && open_areas.same(cell(start), cell(end)) {
+            return Some(step_no);
+        }
+    }
+    None
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -183,3 +239,27 @@ fn do_dijkstra(matrix: Matrix, start: Position, end: Position) {
         println!("No path found!");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "5,4\n4,2\n4,5\n3,0\n2,1\n6,3\n2,4\n1,5\n0,6\n3,3\n2,6\n5,1\n1,2\n5,5\n2,5\n6,5\n1,4\n0,4\n6,4\n1,1\n6,1\n1,0\n0,5\n1,6\n2,0";
+
+    #[test]
+    fn test_first_blocking_obstacle() {
+        let obstacles = get_obstacles(EXAMPLE);
+        let step_no = first_blocking_obstacle(7, 7, &obstacles, (0, 0), (6, 6)).unwrap();
+        assert_eq!(step_no, 20);
+        // Stored as (row, col), the puzzle wants it as x,y.
+        assert_eq!(obstacles[step_no], (1, 6));
+    }
+
+    #[test]
+    fn test_never_blocked() {
+        // Only the first 12 bytes of the example leave a way through.
+        let obstacles = get_obstacles(EXAMPLE);
+        assert_eq!(first_blocking_obstacle(7, 7, &obstacles[..12], (0, 0), (6, 6)), None);
+        assert_eq!(first_blocking_obstacle(7, 7, &[], (0, 0), (6, 6)), None);
+    }
+}
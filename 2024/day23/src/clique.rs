@@ -0,0 +1,242 @@
+/* The LAN as a graph with one bitset of neighbours per computer. Computers are
+ * numbered in sorted name order, so anything that comes out sorted by number is
+ * sorted by name too and ties always break the same way.
+ *
+ * Maximal cliques come from Bron–Kerbosch with pivoting: R is the clique so
+ * far, P the computers that could still join it and X the ones that could but
+ * were already tried. Picking a pivot u with the most neighbours in P and only
+ * branching on P minus u's neighbours skips every branch that would just find
+ * the same clique again.
+ *
+ * https://en.wikipedia.org/wiki/Bron%E2%80%93Kerbosch_algorithm
+ */
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    fn new(size: usize) -> BitSet {
+        BitSet { words: vec![0; size.div_ceil(64)] }
+    }
+
+    fn insert(&mut self, i: usize) {
+        self.words[i / 64] |= 1 << (i % 64);
+    }
+
+    fn remove(&mut self, i: usize) {
+        self.words[i / 64] &= !(1 << (i % 64));
+    }
+
+    #[cfg(test)]
+    fn contains(&self, i: usize) -> bool {
+        self.words[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    fn and(&self, other: &BitSet) -> BitSet {
+        BitSet { words: self.words.iter().zip(&other.words).map(|(a, b)| a & b).collect() }
+    }
+
+    fn and_not(&self, other: &BitSet) -> BitSet {
+        BitSet { words: self.words.iter().zip(&other.words).map(|(a, b)| a & !b).collect() }
+    }
+
+    fn or(&self, other: &BitSet) -> BitSet {
+        BitSet { words: self.words.iter().zip(&other.words).map(|(a, b)| a | b).collect() }
+    }
+
+    fn count_and(&self, other: &BitSet) -> u32 {
+        self.words.iter().zip(&other.words).map(|(a, b)| (a & b).count_ones()).sum()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(w, &word)| {
+            (0..64).filter(move |bit| word & (1 << bit) != 0).map(move |bit| w * 64 + bit)
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Network {
+    names: Vec<String>,
+    neighbours: Vec<BitSet>,
+}
+
+impl Network {
+    pub fn new(connections: &[(&str, &str)]) -> Network {
+        let mut names: Vec<String> = connections
+            .iter()
+            .flat_map(|&(a, b)| [a.to_string(), b.to_string()])
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let id = |name: &str| names.binary_search_by(|n| n.as_str().cmp(name)).unwrap();
+        let mut neighbours = vec![BitSet::new(names.len()); names.len()];
+        for &(a, b) in connections {
+            let (a, b) = (id(a), id(b));
+            // A computer wired to itself isn't any more connected.
+            if a != b {
+                neighbours[a].insert(b);
+                neighbours[b].insert(a);
+            }
+        }
+        Network { names, neighbours }
+    }
+
+    pub fn names(&self, clique: &[usize]) -> Vec<&str> {
+        clique.iter().map(|&i| self.names[i].as_str()).collect()
+    }
+
+    /// Every clique that can't have anyone else added to it, each one sorted.
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let mut cliques = Vec::new();
+        let mut everyone = BitSet::new(self.names.len());
+        for i in 0..self.names.len() {
+            everyone.insert(i);
+        }
+        self.expand(&mut Vec::new(), everyone, BitSet::new(self.names.len()), &mut cliques);
+        cliques.sort();
+        cliques
+    }
+
+    fn expand(&self, clique: &mut Vec<usize>, mut candidates: BitSet, mut excluded: BitSet, found: &mut Vec<Vec<usize>>) {
+        if candidates.is_empty() {
+            if excluded.is_empty() {
+                let mut sorted = clique.clone();
+                sorted.sort();
+                found.push(sorted);
+            }
+            return;
+        }
+
+        let pivot = candidates
+            .or(&excluded)
+            .iter()
+            .max_by_key(|&u| (candidates.count_and(&self.neighbours[u]), std::cmp::Reverse(u)))
+            .unwrap();
+
+        for v in candidates.and_not(&self.neighbours[pivot]).iter().collect::<Vec<_>>() {
+            clique.push(v);
+            self.expand(
+                clique,
+                candidates.and(&self.neighbours[v]),
+                excluded.and(&self.neighbours[v]),
+                found,
+            );
+            clique.pop();
+            candidates.remove(v);
+            excluded.insert(v);
+        }
+    }
+
+    /// The biggest clique. If there's more than one that big, the one that
+    /// comes first alphabetically, so the answer never depends on hash order.
+    pub fn maximum_clique(&self) -> Vec<usize> {
+        self.maximal_cliques()
+            .into_iter()
+            .min_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)))
+            .unwrap_or_default()
+    }
+
+    /// Every clique of exactly `size` computers with at least one whose name
+    /// passes `wanted`. Not just maximal ones, any `size` of them that are all
+    /// connected. Part 1 is size 3 with a name starting with t.
+    pub fn cliques_of_size(&self, size: usize, wanted: impl Fn(&str) -> bool) -> Vec<Vec<usize>> {
+        let mut found = Vec::new();
+        let mut clique = Vec::with_capacity(size);
+        let mut everyone = BitSet::new(self.names.len());
+        for i in 0..self.names.len() {
+            everyone.insert(i);
+        }
+        self.grow(&mut clique, everyone, size, &wanted, &mut found);
+        found
+    }
+
+    // Only ever add computers numbered higher than the last one so each clique
+    // gets built exactly once, in sorted order.
+    fn grow(
+        &self,
+        clique: &mut Vec<usize>,
+        candidates: BitSet,
+        size: usize,
+        wanted: &impl Fn(&str) -> bool,
+        found: &mut Vec<Vec<usize>>,
+    ) {
+        if clique.len() == size {
+            if clique.iter().any(|&i| wanted(&self.names[i])) {
+                found.push(clique.clone());
+            }
+            return;
+        }
+        for v in candidates.iter() {
+            let mut later = candidates.and(&self.neighbours[v]);
+            for earlier in 0..=v {
+                later.remove(earlier);
+            }
+            clique.push(v);
+            self.grow(clique, later, size, wanted, found);
+            clique.pop();
+        }
+    }
+
+    /// Everyone in the clique in alphabetical order, joined with commas.
+    pub fn password(&self, clique: &[usize]) -> String {
+        self.names(clique).join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "kh-tc\nqp-kh\nde-cg\nka-co\nyn-aq\nqp-ub\ncg-tb\nvc-aq\ntb-ka\nwh-tc\nyn-cg\nkh-ub\nta-co\nde-co\ntc-td\ntb-wq\nwh-td\nta-ka\ntd-qp\naq-cg\nwq-ub\nub-vc\nde-ta\nwq-aq\nwq-vc\nwh-yn\nka-de\nkh-ta\nco-tc\nwh-qp\ntb-vc\ntd-yn";
+
+    fn example() -> Network {
+        let connections: Vec<(&str, &str)> = EXAMPLE.lines().map(|line| line.split_once('-').unwrap()).collect();
+        Network::new(&connections)
+    }
+
+    #[test]
+    fn test_triangles() {
+        let network = example();
+        assert_eq!(network.cliques_of_size(3, |_| true).len(), 12);
+        let with_t = network.cliques_of_size(3, |name| name.starts_with('t'));
+        assert_eq!(with_t.len(), 7);
+        assert!(with_t.iter().any(|c| network.names(c) == ["co", "de", "ta"]));
+    }
+
+    #[test]
+    fn test_maximum_clique() {
+        let network = example();
+        let biggest = network.maximum_clique();
+        assert_eq!(network.password(&biggest), "co,de,ka,ta");
+
+        // Every maximal clique really is a clique and really is maximal.
+        for clique in network.maximal_cliques() {
+            for (i, &a) in clique.iter().enumerate() {
+                for &b in &clique[i + 1..] {
+                    assert!(network.neighbours[a].contains(b));
+                }
+            }
+            let everyone = 0..network.names.len();
+            assert!(!everyone
+                .filter(|v| !clique.contains(v))
+                .any(|v| clique.iter().all(|&c| network.neighbours[c].contains(v))));
+        }
+    }
+
+    #[test]
+    fn test_ties_pick_the_first_alphabetically() {
+        // Two triangles, nothing else, so both are the biggest.
+        let network = Network::new(&[("zz", "yy"), ("yy", "xx"), ("xx", "zz"), ("cc", "bb"), ("bb", "aa"), ("aa", "cc")]);
+        assert_eq!(network.maximal_cliques().len(), 2);
+        assert_eq!(network.password(&network.maximum_clique()), "aa,bb,cc");
+        assert_eq!(Network::new(&[]).maximum_clique(), Vec::<usize>::new());
+    }
+}
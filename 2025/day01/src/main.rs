@@ -1,53 +1,104 @@
 use std::fs;
 
 fn main() {
-    part1();
-    part2();
-}
-
-fn part1() {
-    // 0 - 99 in order
-    // clicks each time
-    // L--, R++
-    //11 -> R8 -> 19
-    // 0 left wraps to 99, 99 -> 0/
-    // dial starts at 50
-    // password is how many times it hits 0.
-    let mut start = 50;
-    let mut clicks = 0;
     let raw_data = fs::read_to_string("./input").expect("bad input data");
-    let operations = raw_data.lines().filter(|line| !line.is_empty());
-    for op in operations {
-        let mut turns = 1;
-        match op.chars().take(1).last().unwrap() {
-            'R' => {
-                let num:i32 = op[1..].parse().unwrap();
-                for i in 0..num {
-                    start+=1;
-                    if start == 100 {
-                        start = 0
-                    }
-                }
-            }
-            'L' => {
-                let num: i32 = op[1..].parse().unwrap();
-                for i in 0..num {
-                    start-=1;
-                    if start == -1 {
-                        start = 99
-                    }
-                }
-            },
-            _ => todo!()
+    let rotations = match parse(&raw_data) {
+        Ok(rotations) => rotations,
+        Err(problem) => {
+            println!("{:?}", problem);
+            return;
         }
-        if start == 0 {
-            clicks = clicks + turns;
+    };
+    part1(&rotations);
+    part2(&rotations);
+}
+
+#[derive(Debug, PartialEq)]
+enum InstructionError {
+    UnknownDirection(String),
+    BadAmount(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Rotation {
+    Left(u128),
+    Right(u128),
+}
+
+impl TryFrom<&str> for Rotation {
+    type Error = InstructionError;
+
+    // L68, R48 and so on. The amount can be as big as a u128 can hold.
+    fn try_from(op: &str) -> Result<Rotation, InstructionError> {
+        let op = op.trim();
+        let mut chars = op.chars();
+        let direction = chars.next();
+        let amount: u128 = chars
+            .as_str()
+            .parse()
+            .map_err(|_| InstructionError::BadAmount(op.to_string()))?;
+        match direction {
+            Some('L') => Ok(Rotation::Left(amount)),
+            Some('R') => Ok(Rotation::Right(amount)),
+            _ => Err(InstructionError::UnknownDirection(op.to_string())),
         }
     }
-    println!("{:?}", clicks);
 }
 
-fn part2() {
+fn parse(raw_data: &str) -> Result<Vec<Rotation>, InstructionError> {
+    raw_data
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(Rotation::try_from)
+        .collect()
+}
+
+/* The dial used to be turned one click at a time, which is fine for R48 and
+ * not so fine for R1000000000000. Every 0 we go by is just a division:
+ *
+ *   turning right from p by n hits 0 at p + n = size, 2 * size, ...
+ *   turning left from p by n hits 0 at n = p, p + size, ... (or size, 2 * size if p is 0)
+ */
+#[derive(Debug, Clone, Copy)]
+struct Dial {
+    size: u128,
+    position: u128,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Turn {
+    // How many clicks left the dial pointing at 0, the last one included.
+    zero_passes: u128,
+    landed_on_zero: bool,
+}
+
+impl Dial {
+    fn new(size: u128, start: u128) -> Dial {
+        assert!(size > 0, "a dial needs at least one number on it");
+        Dial { size, position: start % size }
+    }
+
+    fn rotate(&mut self, rotation: Rotation) -> Turn {
+        let (zero_passes, position) = match rotation {
+            Rotation::Right(n) => (
+                (self.position + n % self.size) / self.size + n / self.size,
+                (self.position + n % self.size) % self.size,
+            ),
+            Rotation::Left(n) => {
+                // Clicks until we first reach 0 going left.
+                let to_zero = (self.size - self.position) % self.size;
+                (
+                    (to_zero + n % self.size) / self.size + n / self.size,
+                    (self.position + self.size - n % self.size) % self.size,
+                )
+            }
+        };
+        self.position = position;
+        Turn { zero_passes, landed_on_zero: position == 0 }
+    }
+}
+
+fn part1(rotations: &[Rotation]) {
     // 0 - 99 in order
     // clicks each time
     // L--, R++
@@ -55,41 +106,84 @@ fn part2() {
     // 0 left wraps to 99, 99 -> 0/
     // dial starts at 50
     // password is how many times it hits 0.
-    let mut start = 50;
-    let mut clicks = 0;
-    let raw_data = fs::read_to_string("./input").expect("bad input data");
-    let operations = raw_data.lines().filter(|line| !line.is_empty());
-    for op in operations {
-        let mut turns = 0;
-        match op.chars().take(1).last().unwrap() {
-            'R' => {
-                let num:i32 = op[1..].parse().unwrap();
-                for _ in 0..num {
-                    start+=1;
-                    if start == 100 {
-                        start = 0;
-                    }
-                    if start == 0 {
-                        turns += 1;
-                    }
+    let mut dial = Dial::new(100, 50);
+    let clicks = rotations
+        .iter()
+        .filter(|&&rotation| dial.rotate(rotation).landed_on_zero)
+        .count();
+    println!("{:?}", clicks);
+}
+
+fn part2(rotations: &[Rotation]) {
+    // Same dial, but now every time it points at 0 counts, not just where it stops.
+    let mut dial = Dial::new(100, 50);
+    let clicks: u128 = rotations
+        .iter()
+        .map(|&rotation| dial.rotate(rotation).zero_passes)
+        .sum();
+    println!("{:?}", clicks);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The one click at a time version, to check the maths against.
+    fn click_by_click(size: u128, start: u128, rotations: &[Rotation]) -> (usize, u128) {
+        let mut position = start;
+        let (mut landings, mut passes) = (0, 0);
+        for rotation in rotations {
+            let (n, step) = match *rotation {
+                Rotation::Left(n) => (n, size - 1),
+                Rotation::Right(n) => (n, 1),
+            };
+            for _ in 0..n {
+                position = (position + step) % size;
+                if position == 0 {
+                    passes += 1;
                 }
             }
-            'L' => {
-                let num: i32 = op[1..].parse().unwrap();
-                for _ in 0..num {
-                    start-=1;
-                    if start == -1 {
-                        start = 99;
-                    }
-                    if start == 0 {
-                        turns += 1;
-                    }
-                }
-            },
-            _ => todo!()
+            if position == 0 {
+                landings += 1;
+            }
         }
-        clicks = clicks + turns;
+        (landings, passes)
+    }
 
+    fn run(size: u128, start: u128, rotations: &[Rotation]) -> (usize, u128) {
+        let mut dial = Dial::new(size, start);
+        let turns: Vec<Turn> = rotations.iter().map(|&r| dial.rotate(r)).collect();
+        (
+            turns.iter().filter(|t| t.landed_on_zero).count(),
+            turns.iter().map(|t| t.zero_passes).sum(),
+        )
     }
-    println!("{:?}", clicks);
-}
\ No newline at end of file
+
+    #[test]
+    fn test_example() {
+        let rotations = parse("L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82\n").unwrap();
+        assert_eq!(run(100, 50, &rotations), (3, 6));
+    }
+
+    #[test]
+    fn test_matches_click_by_click() {
+        let rotations = parse("R1000\nL250\nL0\nR3\nL7\nR17\nL13\nL20\nR400\nL1").unwrap();
+        for (size, start) in [(100, 50), (100, 0), (7, 3), (1, 0), (13, 12)] {
+            assert_eq!(run(size, start, &rotations), click_by_click(size, start, &rotations));
+        }
+    }
+
+    #[test]
+    fn test_huge_rotation() {
+        let mut dial = Dial::new(100, 50);
+        let turn = dial.rotate(Rotation::try_from("R100000000000000000000050").unwrap());
+        assert_eq!(turn, Turn { zero_passes: 1000000000000000000001, landed_on_zero: true });
+    }
+
+    #[test]
+    fn test_bad_instructions() {
+        assert_eq!(parse("R5\nX3"), Err(InstructionError::UnknownDirection("X3".to_string())));
+        assert_eq!(parse("L"), Err(InstructionError::BadAmount("L".to_string())));
+        assert_eq!(parse("R-4"), Err(InstructionError::BadAmount("R-4".to_string())));
+    }
+}
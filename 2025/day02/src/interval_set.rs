@@ -0,0 +1,102 @@
+/* The id ranges as sorted, non overlapping, non touching inclusive ranges.
+ * The input doesn't promise its ranges don't overlap, and an id that's in two
+ * of them is still only one invalid id, so squish them together on the way in
+ * and each range can be counted on its own after that.
+ *
+ * Cut down from day05's IntervalSet to just what this day needs.
+ */
+
+/// The integer types an `IntervalSet` can hold. `next` is `None` at the top
+/// of the type so nothing ever overflows.
+pub trait Discrete: Copy + Ord + std::fmt::Debug {
+    fn next(self) -> Option<Self>;
+}
+
+macro_rules! discrete {
+    ($($t:ty),*) => {
+        $(impl Discrete for $t {
+            fn next(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+        })*
+    };
+}
+discrete!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Ord, Eq, PartialOrd)]
+pub struct Range<T> {
+    pub low: T,
+    pub high: T,
+}
+
+impl<T: Discrete> Range<T> {
+    pub fn new(low: T, high: T) -> Range<T> {
+        assert!(low <= high, "range {:?}-{:?} is backwards", low, high);
+        Range { low, high }
+    }
+
+    // True if the other range is completely below us with at least one gap between.
+    fn is_before(&self, other: &Range<T>) -> bool {
+        self.high.next().is_some_and(|after| after < other.low)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Discrete> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet { ranges: Vec::new() };
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        // Everything from `start` up to `end` overlaps or touches the new range
+        // and gets squished into it.
+        let start = self.ranges.partition_point(|r| r.is_before(&range));
+        let end = self.ranges.partition_point(|r| !range.is_before(r));
+
+        let mut merged = range;
+        if start < end {
+            merged.low = merged.low.min(self.ranges[start].low);
+            merged.high = merged.high.max(self.ranges[end - 1].high);
+        }
+        self.ranges.splice(start..end, [merged]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(i64, i64)]) -> IntervalSet<i64> {
+        ranges.iter().map(|&(low, high)| Range::new(low, high)).collect()
+    }
+
+    #[test]
+    fn test_insert_merges_overlapping_and_touching() {
+        let s = set(&[(10, 14), (3, 5), (16, 20), (12, 18), (6, 6)]);
+        assert_eq!(s.ranges(), &[Range::new(3, 6), Range::new(10, 20)]);
+        // A gap of one is still a gap.
+        let s = set(&[(1, 2), (4, 5)]);
+        assert_eq!(s.ranges(), &[Range::new(1, 2), Range::new(4, 5)]);
+    }
+
+    #[test]
+    fn test_no_overflow_at_the_edges() {
+        let mut s: IntervalSet<u8> = [Range::new(250, 255), Range::new(0, 3)].into_iter().collect();
+        s.insert(Range::new(4, 249));
+        assert_eq!(s.ranges(), &[Range::new(0, 255)]);
+    }
+}
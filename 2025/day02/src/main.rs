@@ -1,46 +1,105 @@
 use std::fs;
 
+mod interval_set;
+use interval_set::{IntervalSet, Range};
+
 fn main() {
     let raw_data = fs::read_to_string("./input").expect("bad input data");
     let raw_data = raw_data.as_str();
-    let mut result = 0;
-    for item in raw_data.trim().split(",") {
-        // get the numbers, the problem says no leading 0s but 
-        // just in case, be ready for a string if we need it I guess.
-        let mut iter = item.split("-");
-        let first_str = iter.next().unwrap();
-        let first_num: usize = first_str.parse().expect("bad number 1");
+    let ids = parse_ranges(raw_data);
 
-        let last_str = iter.next().unwrap();
-        let last_num: usize = last_str.parse().expect("bad number 2");
+    // Part 1 is a pattern repeated exactly twice, part 2 is repeated any number of times.
+    let (_, result) = invalid_ids_in(&ids, |repeats| repeats == 2);
+    println!("{:?}", result);
+    let (_, result) = invalid_ids_in(&ids, |repeats| repeats >= 2);
+    println!("{:?}", result);
+}
 
-        for id in first_num..=last_num {
-            if is_invalid_p1(&id.to_string()) {
-                result += id
-            }
+fn invalid_ids_in(ids: &IntervalSet<usize>, allowed_repeats: impl Fn(u32) -> bool) -> (u128, u128) {
+    ids.ranges().iter().fold((0, 0), |(count, sum), range| {
+        let (c, s) = repeated_in_range(range.low as u128, range.high as u128, &allowed_repeats);
+        (count + c, sum + s)
+    })
+}
+
+/* Walking every id is hopeless once a range spans billions, but the invalid
+ * ones are easy to build directly. A d digit pattern P repeated k times is
+ *
+ *   P * (1 + 10^d + 10^2d + ... + 10^(k-1)d)
+ *
+ * so 1212 is 12 * 101 and 123123123 is 123 * 1001001. For each length and each
+ * allowed k the P's that land in the range are one contiguous run, which we can
+ * count and sum without looking at them.
+ *
+ * The catch is 222222 is "2" six times, "22" three times AND "222" twice. A
+ * number made of period d and period e is also made of period gcd(d, e), so
+ * inclusion exclusion over the periods gets each number counted exactly once.
+ *
+ * Returns (how many, their sum).
+ */
+fn repeated_in_range(low: u128, high: u128, allowed_repeats: impl Fn(u32) -> bool) -> (u128, u128) {
+    let mut count = 0i128;
+    let mut sum = 0i128;
+
+    let max_length = high.checked_ilog10().unwrap_or(0) + 1;
+    for length in 1..=max_length {
+        let periods: Vec<u32> = (1..length)
+            .filter(|d| length.is_multiple_of(*d) && allowed_repeats(length / d))
+            .collect();
+
+        for subset in 1..(1u32 << periods.len()) {
+            let chosen = periods.iter().enumerate().filter(|(i, _)| subset & (1 << i) != 0);
+            let period = chosen.clone().fold(0, |acc, (_, &d)| gcd(acc, d));
+            let sign = if chosen.count() % 2 == 1 { 1 } else { -1 };
+
+            let (c, s) = with_period(low, high, length, period);
+            count += sign * c as i128;
+            sum += sign * s as i128;
         }
     }
-    println!("{:?}", result);
-    let mut result = 0;
-    for item in raw_data.trim().split(",") {
+
+    (count as u128, sum as u128)
+}
+
+// Every `length` digit number in low..=high that is one `period` digit pattern over and over.
+fn with_period(low: u128, high: u128, length: u32, period: u32) -> (u128, u128) {
+    let multiplier = (10u128.pow(length) - 1) / (10u128.pow(period) - 1);
+    // No leading zeros, so the pattern itself has to be a full `period` digits long.
+    let smallest = (10u128.pow(period - 1)).max(low.div_ceil(multiplier));
+    let largest = (10u128.pow(period) - 1).min(high / multiplier);
+    if smallest > largest {
+        return (0, 0);
+    }
+
+    let count = largest - smallest + 1;
+    let pattern_sum = if count.is_multiple_of(2) {
+        (count / 2) * (smallest + largest)
+    } else {
+        count * ((smallest + largest) / 2)
+    };
+    (count, pattern_sum * multiplier)
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+// Overlapping ranges get merged so no id is ever counted twice.
+fn parse_ranges(raw_data: &str) -> IntervalSet<usize> {
+    raw_data.trim().split(",").map(|item| {
         // get the numbers, the problem says no leading 0s but 
         // just in case, be ready for a string if we need it I guess.
-        let mut iter = item.split("-");
+        let mut iter = item.trim().split("-");
         let first_str = iter.next().unwrap();
         let first_num: usize = first_str.parse().expect("bad number 1");
 
         let last_str = iter.next().unwrap();
         let last_num: usize = last_str.parse().expect("bad number 2");
-
-        for id in first_num..=last_num {
-            if is_invalid_p2(&id.to_string()) {
-                result += id
-            }
-        }
-    }
-    println!("{:?}", result);
+        Range::new(first_num, last_num)
+    }).collect()
 }
 
+#[cfg(test)]
 fn is_invalid_p2(id: &str) -> bool {
     for window_size in 1..id.len() {
 
@@ -74,6 +133,7 @@ fn is_invalid_p2(id: &str) -> bool {
 }
 
 
+#[cfg(test)]
 fn is_invalid_p1(id: &str) -> bool {
     if id.len() % 2 == 1 {
         return false
@@ -102,4 +162,60 @@ fn is_invalid_p1(id: &str) -> bool {
         return true
     }
     false
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(low: u128, high: u128, is_invalid: fn(&str) -> bool) -> (u128, u128) {
+        (low..=high)
+            .filter(|id| is_invalid(&id.to_string()))
+            .fold((0, 0), |(count, sum), id| (count + 1, sum + id))
+    }
+
+    #[test]
+    fn test_example() {
+        let ids = parse_ranges("11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124");
+        assert_eq!(invalid_ids_in(&ids, |k| k == 2).1, 1227775554);
+        assert_eq!(invalid_ids_in(&ids, |k| k >= 2).1, 4174379265);
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        for (low, high) in [(1, 1), (1, 10000), (95, 115), (99990, 150000), (222220, 222224)] {
+            assert_eq!(repeated_in_range(low, high, |k| k == 2), brute_force(low, high, is_invalid_p1));
+            assert_eq!(repeated_in_range(low, high, |k| k >= 2), brute_force(low, high, is_invalid_p2));
+        }
+    }
+
+    #[test]
+    fn test_wide_range() {
+        // Every 2..=20 digit number, far too many to walk one at a time. There are
+        // 9 * 10^(d-1) patterns of each length d, except the 10 digit patterns stop
+        // where doubling them up would go past u64::MAX.
+        let (count, sum) = repeated_in_range(10, u64::MAX as u128, |k| k == 2);
+        let twenty_digit = (u64::MAX as u128) / (10u128.pow(10) + 1) - 10u128.pow(9) + 1;
+        assert_eq!(count, (0..9).map(|e| 9 * 10u128.pow(e)).sum::<u128>() + twenty_digit);
+        // Each pattern P of d digits shows up once as P * (10^d + 1), so the sum
+        // is (10^d + 1) times the sum of a run of whole numbers.
+        let run = |first: u128, last: u128| (first + last) * (last - first + 1) / 2;
+        let expected: u128 = (1..=10u32)
+            .map(|d| {
+                let first = 10u128.pow(d - 1);
+                let last = if d == 10 { first + twenty_digit - 1 } else { 10u128.pow(d) - 1 };
+                (10u128.pow(d) + 1) * run(first, last)
+            })
+            .sum();
+        assert_eq!(sum, expected);
+    }
+
+    #[test]
+    fn test_every_id_up_to_a_million() {
+        // Small enough to walk, big enough that the repeats overlap (111111 is
+        // "1" six times, "11" three times and "111" twice).
+        let high = 10u128.pow(6);
+        assert_eq!(repeated_in_range(10, high, |k| k == 2), brute_force(10, high, is_invalid_p1));
+        assert_eq!(repeated_in_range(10, high, |k| k >= 2), brute_force(10, high, is_invalid_p2));
+    }
+}
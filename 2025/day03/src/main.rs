@@ -1,3 +1,4 @@
+use std::fmt;
 use std::fs;
 
 fn main() {
@@ -8,82 +9,174 @@ fn main() {
 }
 
 fn p1(input: &str) {
-    let joltage: usize = input.lines().map(|line| {
-        if line.is_empty() {
-            0
-        } else {
-            compute_joltage_p1(line)
-        }
-    }).sum();
-    println!("{:?}", joltage);
-}   
+    println!("{}", total_joltage(input, 2));
+}
 
 fn p2(input: &str) {
-    let joltage: usize = input.lines().map(|line| {
-        if line.is_empty() {
-            0
-        } else {
-            compute_joltage(line, 12)
+    println!("{}", total_joltage(input, 12));
+}
+
+// Added up in decimal a digit at a time, so a selection too big for a u128
+// still counts.
+fn total_joltage(input: &str, allowed_batteries_on: usize) -> String {
+    // Least significant digit first.
+    let mut total: Vec<u8> = Vec::new();
+    for line in input.lines().filter(|line| !line.is_empty()) {
+        let selection = pick_batteries(line, allowed_batteries_on).unwrap_or_else(|problem| {
+            println!("{:?} in {:?}, skipping the bad bits", problem, line);
+            pick_batteries_lenient(line, allowed_batteries_on)
+        });
+        let digits: Vec<u8> = selection.digits.iter().rev().copied().collect();
+        let mut carry = 0;
+        let mut i = 0;
+        while i < digits.len() || carry > 0 {
+            if i == total.len() {
+                total.push(0);
+            }
+            let column = total[i] + digits.get(i).copied().unwrap_or(0) + carry;
+            total[i] = column % 10;
+            carry = column / 10;
+            i += 1;
         }
-    }).sum();
-    println!("{:?}", joltage);
+    }
+    while total.len() > 1 && total.last() == Some(&0) {
+        total.pop();
+    }
+    if total.is_empty() {
+        return String::from("0");
+    }
+    total.iter().rev().map(|&d| (d + b'0') as char).collect()
 }
 
-fn compute_joltage_p1(line: &str) -> usize {
-    let mut joltage = 0;
-    let bank_length = line.len();
+#[derive(Debug, PartialEq)]
+enum BankError {
+    InvalidBattery(usize, char),
+    NotEnoughBatteries { wanted: usize, have: usize },
+}
 
-    for (left_idx, left) in line.chars().enumerate() {
-        if left == '0' {
-            continue;
-        }
-        // Skip it if you can't turn on N batteries anyway
-        if left_idx > bank_length - 2 {
-            continue;
-        }
-        let mut banks_on = String::new();
-        banks_on.push(left);
-        for right in line.chars().skip(left_idx + 1) {
-            let combined: usize = format!("{}{}", left, right).parse().expect("Cant convert left and right to number");
-            if combined > joltage {
-                joltage = combined;
-            }
+/// Which batteries got turned on and the joltage they make together.
+#[derive(Debug, Clone, PartialEq)]
+struct Selection {
+    // Index into the bank (in chars) of each battery that's on, left to right.
+    positions: Vec<usize>,
+    digits: Vec<u8>,
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &d in &self.digits {
+            write!(f, "{}", (d + b'0') as char)?;
         }
+        Ok(())
+    }
+}
+
+/// Turn on exactly `allowed_batteries_on` batteries for the biggest joltage.
+/// Anything that isn't a digit, or too few batteries, is an error.
+fn pick_batteries(battery_bank: &str, allowed_batteries_on: usize) -> Result<Selection, BankError> {
+    let batteries = battery_bank
+        .chars()
+        .enumerate()
+        .map(|(idx, c)| match c.to_digit(10) {
+            Some(d) => Ok((idx, d as u8)),
+            None => Err(BankError::InvalidBattery(idx, c)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if batteries.len() < allowed_batteries_on {
+        return Err(BankError::NotEnoughBatteries {
+            wanted: allowed_batteries_on,
+            have: batteries.len(),
+        });
     }
-    joltage
+    Ok(pick_from(&batteries, allowed_batteries_on))
 }
 
-fn compute_joltage(battery_bank: &str, allowed_batteries_on: usize) -> usize {
-    let mut enabled_digits = Vec::with_capacity(allowed_batteries_on);
-    let digits = battery_bank.chars().enumerate();
-    let length = battery_bank.chars().count();
+/// Same as pick_batteries but skips over anything that isn't a digit, and if
+/// asked for more batteries than there are it just turns them all on.
+fn pick_batteries_lenient(battery_bank: &str, allowed_batteries_on: usize) -> Selection {
+    let batteries: Vec<(usize, u8)> = battery_bank
+        .chars()
+        .enumerate()
+        .filter_map(|(idx, c)| c.to_digit(10).map(|d| (idx, d as u8)))
+        .collect();
+    let allowed_batteries_on = allowed_batteries_on.min(batteries.len());
+    pick_from(&batteries, allowed_batteries_on)
+}
 
-    for (idx, battery_character) in digits {
-        let battery_value = battery_character as u8 - b'0';
+/* Monotonic stack. Walk the bank and whenever a battery beats the one on top
+ * of the stack, pop it, as long as there are enough batteries left to still
+ * fill all the slots. Works for part 1 (k = 2) just as well as part 2, so no
+ * more trying every pair.
+ */
+fn pick_from(batteries: &[(usize, u8)], allowed_batteries_on: usize) -> Selection {
+    let mut enabled: Vec<(usize, u8)> = Vec::with_capacity(allowed_batteries_on);
+    let length = batteries.len();
 
-        while let Some(&top) = enabled_digits.last() {
+    for (i, &(idx, battery_value)) in batteries.iter().enumerate() {
+        while let Some(&(_, top)) = enabled.last() {
 
-            let enabled_count = enabled_digits.len();
-            let remaining_digit_count = length - idx;
+            let enabled_count = enabled.len();
+            let remaining_digit_count = length - i;
             let battery_provides_better_joltage = top < battery_value;
             let can_still_fill_bank_to_limit = enabled_count + remaining_digit_count > allowed_batteries_on;
 
             if battery_provides_better_joltage && can_still_fill_bank_to_limit {
-                enabled_digits.pop();
+                enabled.pop();
             } else {
                 break;
             }
         }
 
-        if enabled_digits.len() < allowed_batteries_on {
-            enabled_digits.push(battery_value);
+        if enabled.len() < allowed_batteries_on {
+            enabled.push((idx, battery_value));
         }
     }
 
+    Selection {
+        positions: enabled.iter().map(|&(idx, _)| idx).collect(),
+        digits: enabled.iter().map(|&(_, d)| d).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut combined = String::with_capacity(allowed_batteries_on);
-    for battery in enabled_digits {
-        combined.push((battery + b'0') as char);
+    const EXAMPLE: &str = "987654321111111\n811111111111119\n234234234234278\n818181911112111\n";
+
+    #[test]
+    fn test_example() {
+        assert_eq!(total_joltage(EXAMPLE, 2), "357");
+        assert_eq!(total_joltage(EXAMPLE, 12), "3121910778619");
     }
-    combined.parse().expect("joltage did not convert")
-}
\ No newline at end of file
+
+    #[test]
+    fn test_positions() {
+        let picked = pick_batteries("818181911112111", 2).unwrap();
+        assert_eq!(picked.positions, vec![6, 11]);
+        assert_eq!(picked.to_string(), "92");
+    }
+
+    #[test]
+    fn test_more_digits_than_fit() {
+        let bank = "9".repeat(45) + "1";
+        let picked = pick_batteries(&bank, 40).unwrap();
+        assert_eq!(picked.to_string(), "9".repeat(40));
+        assert_eq!(picked.positions, (0..40).collect::<Vec<_>>());
+        // Two of them go past u128 and carry all the way up.
+        let banks = format!("{}\n{}\n", bank, bank);
+        assert_eq!(total_joltage(&banks, 40), format!("1{}8", "9".repeat(39)));
+        assert_eq!(total_joltage("", 2), "0");
+    }
+
+    #[test]
+    fn test_bad_banks() {
+        assert_eq!(pick_batteries("12x4", 2), Err(BankError::InvalidBattery(2, 'x')));
+        assert_eq!(pick_batteries("12", 3), Err(BankError::NotEnoughBatteries { wanted: 3, have: 2 }));
+
+        let picked = pick_batteries_lenient("1 2x9", 5);
+        assert_eq!(picked.positions, vec![0, 2, 4]);
+        assert_eq!(picked.to_string(), "129");
+        assert_eq!(pick_batteries_lenient("1 2x9", 2).to_string(), "29");
+    }
+}
@@ -0,0 +1,228 @@
+/* A set of integers stored as sorted, non overlapping, non touching inclusive
+ * ranges. Every operation leaves it in that shape, so there's never any
+ * "expand each rule against every other rule until it stops changing" like the
+ * first go at part 2 did.
+ */
+
+/// The integer types an `IntervalSet` can hold. `next`/`prev` are `None` at
+/// the ends of the type so nothing ever overflows.
+pub trait Discrete: Copy + Ord + std::fmt::Debug {
+    fn next(self) -> Option<Self>;
+    // Only `remove` needs it.
+    #[allow(dead_code)]
+    fn prev(self) -> Option<Self>;
+    /// How many values are in `low..=high`.
+    fn span(low: Self, high: Self) -> u128;
+}
+
+macro_rules! discrete {
+    ($($t:ty),*) => {
+        $(impl Discrete for $t {
+            fn next(self) -> Option<Self> {
+                self.checked_add(1)
+            }
+            fn prev(self) -> Option<Self> {
+                self.checked_sub(1)
+            }
+            fn span(low: Self, high: Self) -> u128 {
+                (high as i128 - low as i128) as u128 + 1
+            }
+        })*
+    };
+}
+discrete!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Ord, Eq, PartialOrd)]
+pub struct Range<T> {
+    pub low: T,
+    pub high: T,
+}
+
+impl<T: Discrete> Range<T> {
+    pub fn new(low: T, high: T) -> Range<T> {
+        assert!(low <= high, "range {:?}-{:?} is backwards", low, high);
+        Range { low, high }
+    }
+
+    pub fn len(&self) -> u128 {
+        T::span(self.low, self.high)
+    }
+
+    // True if the other range is completely below us with at least one gap between.
+    fn is_before(&self, other: &Range<T>) -> bool {
+        self.high.next().is_some_and(|after| after < other.low)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    ranges: Vec<Range<T>>,
+}
+
+impl<T: Discrete> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Discrete> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(iter: I) -> Self {
+        let mut set = IntervalSet::new();
+        for range in iter {
+            set.insert(range);
+        }
+        set
+    }
+}
+
+impl<T: Discrete> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { ranges: Vec::new() }
+    }
+
+    /// Total number of values covered by all the ranges.
+    pub fn len(&self) -> u128 {
+        self.ranges.iter().map(Range::len).sum()
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        let idx = self.ranges.partition_point(|r| r.high < value);
+        self.ranges.get(idx).is_some_and(|r| r.low <= value)
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        // Everything from `start` up to `end` overlaps or touches the new range
+        // and gets squished into it.
+        let start = self.ranges.partition_point(|r| r.is_before(&range));
+        let end = self.ranges.partition_point(|r| !range.is_before(r));
+
+        let mut merged = range;
+        if start < end {
+            merged.low = merged.low.min(self.ranges[start].low);
+            merged.high = merged.high.max(self.ranges[end - 1].high);
+        }
+        self.ranges.splice(start..end, [merged]);
+    }
+}
+
+// The rest of the set operations. Part 2 gets by on insert, len and contains,
+// these are only exercised by the tests for now.
+#[allow(dead_code)]
+impl<T: Discrete> IntervalSet<T> {
+    pub fn ranges(&self) -> &[Range<T>] {
+        &self.ranges
+    }
+
+    pub fn remove(&mut self, range: Range<T>) {
+        let start = self.ranges.partition_point(|r| r.high < range.low);
+        let end = self.ranges.partition_point(|r| r.low <= range.high);
+        if start >= end {
+            return;
+        }
+
+        // Only the first and last overlapping ranges can have anything left over.
+        let mut leftovers = Vec::new();
+        let first = self.ranges[start];
+        if first.low < range.low {
+            leftovers.push(Range::new(first.low, range.low.prev().unwrap()));
+        }
+        let last = self.ranges[end - 1];
+        if range.high < last.high {
+            leftovers.push(Range::new(range.high.next().unwrap(), last.high));
+        }
+        self.ranges.splice(start..end, leftovers);
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = self.clone();
+        for &range in &other.ranges {
+            result.insert(range);
+        }
+        result
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut ranges = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.ranges.len() && j < other.ranges.len() {
+            let (a, b) = (self.ranges[i], other.ranges[j]);
+            let low = a.low.max(b.low);
+            let high = a.high.min(b.high);
+            if low <= high {
+                ranges.push(Range::new(low, high));
+            }
+            // Whichever ends first can't overlap anything further along.
+            if a.high < b.high {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { ranges }
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut result = self.clone();
+        for &range in &other.ranges {
+            result.remove(range);
+        }
+        result
+    }
+
+    /// Everything inside `bounds` that is not in the set.
+    pub fn complement(&self, bounds: Range<T>) -> IntervalSet<T> {
+        let mut result = IntervalSet::new();
+        result.insert(bounds);
+        result.difference(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(ranges: &[(i64, i64)]) -> IntervalSet<i64> {
+        ranges.iter().map(|&(low, high)| Range::new(low, high)).collect()
+    }
+
+    #[test]
+    fn test_insert_merges_overlapping_and_touching() {
+        let s = set(&[(10, 14), (3, 5), (16, 20), (12, 18), (6, 6)]);
+        assert_eq!(s.ranges(), &[Range::new(3, 6), Range::new(10, 20)]);
+        assert_eq!(s.len(), 4 + 11);
+        assert!(s.contains(6));
+        assert!(!s.contains(7));
+        assert!(s.contains(20));
+        assert!(!s.contains(21));
+    }
+
+    #[test]
+    fn test_remove_splits() {
+        let mut s = set(&[(1, 10), (20, 30)]);
+        s.remove(Range::new(5, 22));
+        assert_eq!(s.ranges(), &[Range::new(1, 4), Range::new(23, 30)]);
+        s.remove(Range::new(100, 200));
+        assert_eq!(s.len(), 4 + 8);
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let a = set(&[(1, 5), (10, 15)]);
+        let b = set(&[(4, 11), (14, 20)]);
+        assert_eq!(a.union(&b), set(&[(1, 20)]));
+        assert_eq!(a.intersection(&b), set(&[(4, 5), (10, 11), (14, 15)]));
+        assert_eq!(a.difference(&b), set(&[(1, 3), (12, 13)]));
+        assert_eq!(a.complement(Range::new(0, 12)), set(&[(0, 0), (6, 9)]));
+    }
+
+    #[test]
+    fn test_no_overflow_at_the_edges() {
+        let mut s: IntervalSet<u8> = [Range::new(250, 255), Range::new(0, 3)].into_iter().collect();
+        s.insert(Range::new(4, 249));
+        assert_eq!(s.ranges(), &[Range::new(0, 255)]);
+        assert_eq!(s.len(), 256);
+        s.remove(Range::new(0, 0));
+        s.remove(Range::new(255, 255));
+        assert_eq!(s.ranges(), &[Range::new(1, 254)]);
+    }
+}
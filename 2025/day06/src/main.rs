@@ -1,112 +1,34 @@
 use std::fs;
 
+mod worksheet;
+use worksheet::{Operators, Reading, Worksheet, WorksheetError};
+
 fn main() {
     let raw_data = fs::read_to_string("./input").expect("bad input data");
-    let raw_data = raw_data.as_str();
-    p1(raw_data);
-    p2(raw_data);
+    // One parse, both parts just read the same blocks differently.
+    let worksheet = match Worksheet::parse(&raw_data, &Operators::standard()) {
+        Ok(worksheet) => worksheet,
+        Err(problem) => {
+            println!("{:?}", problem);
+            return;
+        }
+    };
+    p1(&worksheet);
+    p2(&worksheet);
 }
 
-fn p1(raw_data: &str) {
-    let lines: Vec<Vec<&str>> = raw_data.lines()
-        .take_while(|line| !line.is_empty())
-        .map(|line| {
-            line.trim().split(" ").map(|raw| raw.trim()).filter(|raw| !raw.is_empty()).collect()
-        }).collect();
-
-    let copy = lines.clone();
-    let ops = copy.last().expect("cant get op line");
-    let rows_of_numbers_count = lines.len() - 1;
-
-    let mut cursors: Vec<_> = 
-        lines.into_iter()
-            .take(rows_of_numbers_count)
-            .map(|split_line| split_line.into_iter())
-            .collect();
+fn p1(worksheet: &Worksheet) {
+    show(worksheet.grand_total(Reading::Rows));
+}
 
-    let mut sum = 0;
-    for op in ops {
-        match *op {
-            "+" => {
-                for i in 0..rows_of_numbers_count {
-                    let num: usize = cursors[i].next().expect("no next number").parse().expect("couldnt parse it");
-                    sum += num;
-                }
-            }
-            "*" => {
-                let mut tmp = 1;
-                for i in 0..rows_of_numbers_count {
-                    let num: usize = cursors[i].next().expect("no next number").parse().expect("couldnt parse it");
-                    tmp *= num;
-                }
-                sum += tmp;
-            }
-            _ => unreachable!()
-        }
-    }
-    println!("{:?}", sum);
+fn p2(worksheet: &Worksheet) {
+    // white space is now significant, each column is a number and we go right to left.
+    show(worksheet.grand_total(Reading::Columns));
 }
 
-fn p2(raw_data: &str) {
-    // white space is now significant
-    // but the operator defines the boundary leftmost part which should help.
-    // but maybe the simpler thing to do is to just transpose the input itself
-    
-    let (matrix, rows, cols) = make_matrix(raw_data);
-    // read right to left
-    let mut numbers_in_problem: Vec<usize> = Vec::new();
-    let mut total = 0;
-    for col in (0..cols).rev() {
-        // Most significant digit is at the top, so read top to bottom
-        let mut number = String::with_capacity(rows);
-        for row in 0..rows {
-            // Do we have an operand?
-            let operand_row = row == rows - 1;
-            match (matrix[row][col], operand_row) {
-                (' ', true) => {
-                    // end of the number we're building.
-                    if number.trim().is_empty() {
-                        numbers_in_problem = Vec::new();
-                        continue;
-                    }
-                    let number_value: usize = number.trim().parse().expect("couldnt convert number!");
-                    numbers_in_problem.push(number_value);
-                    number = String::with_capacity(rows);
-                },
-                ('+', true) => {
-                    let number_value: usize = number.trim().parse().expect("couldnt convert number!");
-                    numbers_in_problem.push(number_value);
-                    total += numbers_in_problem.into_iter().sum::<usize>();
-                    numbers_in_problem = Vec::new();
-                    number = String::with_capacity(rows);
-                },
-                ('*', true) => {
-                    let number_value: usize = number.trim().parse().expect("couldnt convert number!");
-                    numbers_in_problem.push(number_value);
-                    total += numbers_in_problem.into_iter().reduce(|a, b| a * b).expect("no numbers to multiply?");
-                    numbers_in_problem = Vec::new();
-                    number = String::with_capacity(rows);
-                },
-                (c, false) => {
-                    number.push(c);
-                },
-                _ => unreachable!()
-            }
-        }
+fn show(total: Result<i128, WorksheetError>) {
+    match total {
+        Ok(total) => println!("{:?}", total),
+        Err(problem) => println!("{:?}", problem),
     }
-
-    println!("{:?}", total);
 }
-
-fn make_matrix(raw_data: &str) -> (Vec<Vec<char>>, usize, usize) {
-        // Parse the matrix
-    let matrix: Vec<Vec<char>> = raw_data
-        .lines()
-        .filter(|line| !line.is_empty())
-        .map(|line| line.chars().collect())
-        .collect();
-
-    let rows = matrix.len();
-    let cols = matrix.get(0).map(|m| m.len()).unwrap_or(0);
-    (matrix, rows, cols)
-}
\ No newline at end of file
@@ -0,0 +1,284 @@
+use std::fmt;
+
+/* The cephalopod worksheet, parsed once with the layout kept intact:
+ *
+ *   123 328  51 64
+ *    45 64  387 23
+ *     6 98  215 314
+ *   *   +   *   +
+ *
+ * A column that's blank all the way down (operator row included) separates two
+ * problems. Inside a problem the numbers can be read the human way, one per
+ * row, or the cephalopod way, one per column going right to left. Either way
+ * each problem turns into an expression tree that gets evaluated at the end.
+ *
+ * A problem with one operator between each pair of numbers has its operators
+ * read the same way as its numbers: left to right under rows, right to left
+ * under columns. So the first operator read always goes between the first two
+ * numbers read.
+ */
+
+#[derive(Debug, PartialEq)]
+pub enum WorksheetError {
+    Empty,
+    RaggedLine { line: usize, width: usize, expected: usize },
+    UnknownOperator { column: usize, symbol: char },
+    BadNumber { problem: usize, text: String },
+    NoNumbers { problem: usize },
+    MissingOperator { problem: usize },
+    // Either one operator for the whole problem or one between each pair of numbers.
+    OperatorCount { problem: usize, operators: usize, numbers: usize },
+    Arithmetic { problem: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Operator {
+    pub symbol: char,
+    pub apply: fn(i128, i128) -> Option<i128>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Operators {
+    known: Vec<Operator>,
+}
+
+impl Operators {
+    // + - * / with overflow and divide by zero coming back as None.
+    pub fn standard() -> Operators {
+        Operators { known: Vec::new() }
+            .with('+', i128::checked_add)
+            .with('-', i128::checked_sub)
+            .with('*', i128::checked_mul)
+            .with('/', i128::checked_div)
+    }
+
+    /// Add another operator, or replace what an existing symbol does.
+    pub fn with(mut self, symbol: char, apply: fn(i128, i128) -> Option<i128>) -> Operators {
+        self.known.retain(|op| op.symbol != symbol);
+        self.known.push(Operator { symbol, apply });
+        self
+    }
+
+    fn get(&self, symbol: char) -> Option<Operator> {
+        self.known.iter().find(|op| op.symbol == symbol).copied()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reading {
+    // Part 1, each row of a problem is a number.
+    Rows,
+    // Part 2, each column is a number, most significant digit on top, right to left.
+    Columns,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Number(i128),
+    Binary {
+        op: Operator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+impl Expr {
+    pub fn eval(&self) -> Option<i128> {
+        match self {
+            Expr::Number(n) => Some(*n),
+            Expr::Binary { op, left, right } => (op.apply)(left.eval()?, right.eval()?),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Binary { op, left, right } => write!(f, "({} {} {})", left, op.symbol, right),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Problem {
+    // Which columns of the worksheet this problem covers, end exclusive.
+    pub columns: std::ops::Range<usize>,
+    operators: Vec<Operator>,
+}
+
+#[derive(Debug)]
+pub struct Worksheet {
+    number_rows: Vec<Vec<char>>,
+    pub problems: Vec<Problem>,
+}
+
+impl Worksheet {
+    pub fn parse(raw_data: &str, operators: &Operators) -> Result<Worksheet, WorksheetError> {
+        let mut lines: Vec<Vec<char>> = raw_data
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.chars().collect())
+            .collect();
+        let operator_row = lines.pop().ok_or(WorksheetError::Empty)?;
+
+        let expected = operator_row.len();
+        for (line, row) in lines.iter().enumerate() {
+            if row.len() != expected {
+                return Err(WorksheetError::RaggedLine { line, width: row.len(), expected });
+            }
+        }
+
+        let is_separator = |col: usize| {
+            operator_row[col] == ' ' && lines.iter().all(|row| row[col] == ' ')
+        };
+
+        let mut problems = Vec::new();
+        let mut col = 0;
+        while col < expected {
+            if is_separator(col) {
+                col += 1;
+                continue;
+            }
+            let start = col;
+            while col < expected && !is_separator(col) {
+                col += 1;
+            }
+
+            let mut ops = Vec::new();
+            for (c, &symbol) in operator_row.iter().enumerate().take(col).skip(start) {
+                if symbol == ' ' {
+                    continue;
+                }
+                ops.push(operators.get(symbol).ok_or(WorksheetError::UnknownOperator { column: c, symbol })?);
+            }
+            problems.push(Problem { columns: start..col, operators: ops });
+        }
+
+        Ok(Worksheet { number_rows: lines, problems })
+    }
+
+    fn numbers(&self, problem: usize, reading: Reading) -> Result<Vec<i128>, WorksheetError> {
+        let columns = self.problems[problem].columns.clone();
+        let texts: Vec<String> = match reading {
+            Reading::Rows => self
+                .number_rows
+                .iter()
+                .map(|row| row[columns.clone()].iter().collect())
+                .collect(),
+            Reading::Columns => columns
+                .rev()
+                .map(|c| self.number_rows.iter().map(|row| row[c]).collect())
+                .collect(),
+        };
+
+        texts
+            .iter()
+            .map(|text| text.trim())
+            .filter(|text| !text.is_empty())
+            .map(|text| {
+                text.parse().map_err(|_| WorksheetError::BadNumber { problem, text: text.to_string() })
+            })
+            .collect()
+    }
+
+    /// The expression tree for one problem, applied in the order the numbers
+    /// are read.
+    pub fn expression(&self, problem: usize, reading: Reading) -> Result<Expr, WorksheetError> {
+        let numbers = self.numbers(problem, reading)?;
+        let mut operators = self.problems[problem].operators.clone();
+        if reading == Reading::Columns {
+            operators.reverse();
+        }
+        let (&first, rest) = numbers.split_first().ok_or(WorksheetError::NoNumbers { problem })?;
+
+        let op_for = |i: usize| match operators.len() {
+            0 => Err(WorksheetError::MissingOperator { problem }),
+            1 => Ok(operators[0]),
+            n if n == numbers.len() - 1 => Ok(operators[i]),
+            n => Err(WorksheetError::OperatorCount { problem, operators: n, numbers: numbers.len() }),
+        };
+
+        let mut expr = Expr::Number(first);
+        for (i, &n) in rest.iter().enumerate() {
+            expr = Expr::Binary {
+                op: op_for(i)?,
+                left: Box::new(expr),
+                right: Box::new(Expr::Number(n)),
+            };
+        }
+        Ok(expr)
+    }
+
+    pub fn expressions(&self, reading: Reading) -> Result<Vec<Expr>, WorksheetError> {
+        (0..self.problems.len()).map(|p| self.expression(p, reading)).collect()
+    }
+
+    /// Every problem's answer added up, the thing the puzzle actually asks for.
+    pub fn grand_total(&self, reading: Reading) -> Result<i128, WorksheetError> {
+        self.expressions(reading)?
+            .iter()
+            .enumerate()
+            .try_fold(0i128, |total, (problem, expr)| {
+                expr.eval()
+                    .and_then(|value| total.checked_add(value))
+                    .ok_or(WorksheetError::Arithmetic { problem })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+    #[test]
+    fn test_example_both_ways() {
+        let worksheet = Worksheet::parse(EXAMPLE, &Operators::standard()).unwrap();
+        assert_eq!(worksheet.problems.len(), 4);
+        assert_eq!(worksheet.grand_total(Reading::Rows), Ok(4277556));
+        assert_eq!(worksheet.grand_total(Reading::Columns), Ok(3263827));
+        assert_eq!(worksheet.expression(0, Reading::Columns).unwrap().to_string(), "((356 * 24) * 1)");
+    }
+
+    #[test]
+    fn test_mixed_and_custom_operators() {
+        let sheet = "20 7 3\n 4 2 1\n 2 1 1\n/- - ^\n";
+        let operators = Operators::standard().with('^', |a, b| a.checked_pow(b.try_into().ok()?));
+        let worksheet = Worksheet::parse(sheet, &operators).unwrap();
+        assert_eq!(worksheet.expression(0, Reading::Rows).unwrap().to_string(), "((20 / 4) - 2)");
+        // 7 - 2 - 1 with the default single operator spread over the whole problem.
+        assert_eq!(worksheet.expression(1, Reading::Rows).unwrap().eval(), Some(4));
+        assert_eq!(worksheet.expression(2, Reading::Rows).unwrap().eval(), Some(3));
+    }
+
+    #[test]
+    fn test_mixed_operators_read_columns_right_to_left() {
+        // Right to left the numbers are 24, 92, 12 and the operators + then -.
+        let worksheet = Worksheet::parse("192\n224\n- +\n", &Operators::standard()).unwrap();
+        let expr = worksheet.expression(0, Reading::Columns).unwrap();
+        assert_eq!(expr.to_string(), "((24 + 92) - 12)");
+        assert_eq!(expr.eval(), Some(104));
+        // Two numbers a row, so two operators is one too many that way.
+        assert!(matches!(
+            worksheet.expression(0, Reading::Rows),
+            Err(WorksheetError::OperatorCount { problem: 0, operators: 2, numbers: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        let operators = Operators::standard();
+        assert!(matches!(
+            Worksheet::parse("12 3\n1\n+  +\n", &operators),
+            Err(WorksheetError::RaggedLine { line: 1, width: 1, expected: 4 })
+        ));
+        assert!(matches!(
+            Worksheet::parse("1 2\n3 4\n+ %\n", &operators),
+            Err(WorksheetError::UnknownOperator { column: 2, symbol: '%' })
+        ));
+        let worksheet = Worksheet::parse("1 2\n0 4\n/ +\n", &operators).unwrap();
+        assert_eq!(worksheet.grand_total(Reading::Rows), Err(WorksheetError::Arithmetic { problem: 0 }));
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt::Write;
+
+/* The tachyon manifold. A beam enters at S and moves down one row at a time.
+ * Whatever it runs into decides where it goes next:
+ *
+ *   .  keeps going straight down
+ *   ^  splits, one beam out each side
+ *   <  deflector, only the left beam comes out
+ *   >  deflector, only the right beam comes out
+ *
+ * Rather than a list of beam columns that gets sorted and deduped after every
+ * split, each row is just a count per column of how many timelines are in it.
+ * Beams merging is the counts adding up, and the number of distinct beams is
+ * the number of non zero columns. Beams that go off the left or right side are
+ * counted on their own instead of quietly dropped (or indexed out of bounds).
+ */
+
+#[derive(Debug, PartialEq)]
+pub enum ManifoldError {
+    NoStart,
+    MultipleStarts { first: (usize, usize), second: (usize, usize) },
+    RaggedRow { row: usize, width: usize, expected: usize },
+    UnknownCell { row: usize, col: usize, symbol: char },
+    // Timelines double on every split, so a tall enough manifold runs out of u128.
+    TooManyTimelines { row: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cell {
+    Empty,
+    Start,
+    Splitter,
+    Deflector(Side),
+}
+
+impl Cell {
+    fn symbol(self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::Start => 'S',
+            Cell::Splitter => '^',
+            Cell::Deflector(Side::Left) => '<',
+            Cell::Deflector(Side::Right) => '>',
+        }
+    }
+
+    // Which ways a beam comes out after running into this cell, none means straight down.
+    fn exits(self) -> &'static [Side] {
+        match self {
+            Cell::Splitter => &[Side::Left, Side::Right],
+            Cell::Deflector(Side::Left) => &[Side::Left],
+            Cell::Deflector(Side::Right) => &[Side::Right],
+            Cell::Empty | Cell::Start => &[],
+        }
+    }
+}
+
+impl TryFrom<char> for Cell {
+    type Error = char;
+
+    fn try_from(symbol: char) -> Result<Cell, char> {
+        match symbol {
+            '.' => Ok(Cell::Empty),
+            'S' => Ok(Cell::Start),
+            '^' => Ok(Cell::Splitter),
+            '<' => Ok(Cell::Deflector(Side::Left)),
+            '>' => Ok(Cell::Deflector(Side::Right)),
+            other => Err(other),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Manifold {
+    cells: Vec<Vec<Cell>>,
+    width: usize,
+    start: (usize, usize),
+}
+
+impl TryFrom<&str> for Manifold {
+    type Error = ManifoldError;
+
+    fn try_from(raw_data: &str) -> Result<Manifold, ManifoldError> {
+        let mut cells = Vec::new();
+        let mut start = None;
+        let mut width = None;
+
+        for (row, line) in raw_data.lines().filter(|line| !line.is_empty()).enumerate() {
+            let parsed = line
+                .chars()
+                .enumerate()
+                .map(|(col, symbol)| Cell::try_from(symbol).map_err(|symbol| ManifoldError::UnknownCell { row, col, symbol }))
+                .collect::<Result<Vec<Cell>, _>>()?;
+
+            let expected = *width.get_or_insert(parsed.len());
+            if parsed.len() != expected {
+                return Err(ManifoldError::RaggedRow { row, width: parsed.len(), expected });
+            }
+
+            for col in (0..parsed.len()).filter(|&col| parsed[col] == Cell::Start) {
+                if let Some(first) = start {
+                    return Err(ManifoldError::MultipleStarts { first, second: (row, col) });
+                }
+                start = Some((row, col));
+            }
+            cells.push(parsed);
+        }
+
+        Ok(Manifold {
+            cells,
+            width: width.unwrap_or(0),
+            start: start.ok_or(ManifoldError::NoStart)?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Propagation {
+    /// How many splitters a beam actually ran into.
+    pub splits: usize,
+    /// `timelines[row][col]` is how many timelines have a beam passing through that cell.
+    pub timelines: Vec<Vec<u128>>,
+    pub exited_bottom: u128,
+    pub escaped_left: u128,
+    pub escaped_right: u128,
+}
+
+impl Propagation {
+    /// Every timeline ends somewhere, the bottom or off one of the sides.
+    pub fn total_timelines(&self) -> u128 {
+        self.exited_bottom + self.escaped_left + self.escaped_right
+    }
+}
+
+impl Manifold {
+    pub fn propagate(&self) -> Result<Propagation, ManifoldError> {
+        let (start_row, start_col) = self.start;
+        let mut timelines = vec![vec![0u128; self.width]; self.cells.len()];
+        timelines[start_row][start_col] = 1;
+
+        let mut splits = 0;
+        let (mut escaped_left, mut escaped_right) = (0u128, 0u128);
+
+        for row in start_row + 1..self.cells.len() {
+            let (above, here) = timelines.split_at_mut(row);
+            let (above, here) = (&above[row - 1], &mut here[0]);
+            let overflow = || ManifoldError::TooManyTimelines { row };
+
+            for col in (0..self.width).filter(|&col| above[col] > 0) {
+                let count = above[col];
+                let cell = self.cells[row][col];
+                if cell == Cell::Splitter {
+                    splits += 1;
+                }
+                if cell.exits().is_empty() {
+                    here[col] = here[col].checked_add(count).ok_or_else(overflow)?;
+                    continue;
+                }
+
+                for side in cell.exits() {
+                    let (target, escaped) = match side {
+                        Side::Left => (col.checked_sub(1), &mut escaped_left),
+                        Side::Right => (Some(col + 1).filter(|&c| c < self.width), &mut escaped_right),
+                    };
+                    match target {
+                        Some(c) => here[c] = here[c].checked_add(count).ok_or_else(overflow)?,
+                        None => *escaped = escaped.checked_add(count).ok_or_else(overflow)?,
+                    }
+                }
+            }
+        }
+
+        // Check the grand total fits too so total_timelines() never has to.
+        let exited_bottom = timelines
+            .last()
+            .map_or(Some(0), |row| row.iter().try_fold(0u128, |total, &n| total.checked_add(n)))
+            .filter(|bottom| bottom.checked_add(escaped_left).and_then(|n| n.checked_add(escaped_right)).is_some())
+            .ok_or(ManifoldError::TooManyTimelines { row: self.cells.len() })?;
+
+        Ok(Propagation { splits, timelines, exited_bottom, escaped_left, escaped_right })
+    }
+
+    /// The manifold with every lit cell drawn as `|`, like the puzzle's pictures.
+    pub fn render(&self, propagation: &Propagation) -> String {
+        let mut picture = String::new();
+        for (row, cells) in self.cells.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                let lit = propagation.timelines[row][col] > 0;
+                picture.push(if lit && cell == Cell::Empty { '|' } else { cell.symbol() });
+            }
+            picture.push('\n');
+        }
+        picture
+    }
+
+    /// Same grid but with the timeline count in every lit cell, padded so the columns line up.
+    pub fn render_counts(&self, propagation: &Propagation) -> String {
+        let cell_width = propagation
+            .timelines
+            .iter()
+            .flatten()
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        let mut picture = String::new();
+        for (row, cells) in self.cells.iter().enumerate() {
+            let line: Vec<String> = cells
+                .iter()
+                .enumerate()
+                .map(|(col, &cell)| match propagation.timelines[row][col] {
+                    0 => format!("{:>cell_width$}", cell.symbol()),
+                    n => format!("{:>cell_width$}", n),
+                })
+                .collect();
+            let _ = writeln!(picture, "{}", line.join(" "));
+        }
+        picture
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "\
+.......S.......
+...............
+.......^.......
+...............
+......^.^......
+...............
+.....^.^.^.....
+...............
+....^.^...^....
+...............
+...^.^...^.^...
+...............
+..^...^.....^..
+...............
+.^.^.^.^.^...^.
+...............
+";
+
+    #[test]
+    fn test_example() {
+        let manifold = Manifold::try_from(EXAMPLE).unwrap();
+        let propagation = manifold.propagate().unwrap();
+        assert_eq!(propagation.splits, 21);
+        assert_eq!(propagation.total_timelines(), 40);
+        assert_eq!(propagation.escaped_left + propagation.escaped_right, 0);
+    }
+
+    #[test]
+    fn test_edges_and_deflectors() {
+        let manifold = Manifold::try_from("S..\n^..\n.>.\n..^\n").unwrap();
+        let propagation = manifold.propagate().unwrap();
+        // Left half of the first split goes straight off the side, the right
+        // half gets deflected right and then split with one half off the edge.
+        assert_eq!(propagation.splits, 2);
+        assert_eq!(propagation.escaped_left, 1);
+        assert_eq!(propagation.escaped_right, 1);
+        assert_eq!(propagation.exited_bottom, 1);
+        assert_eq!(manifold.render(&propagation), "S..\n^|.\n.>|\n.|^\n");
+        assert_eq!(manifold.render_counts(&propagation), "1 . .\n^ 1 .\n. > 1\n. 1 ^\n");
+    }
+
+    #[test]
+    fn test_counts_do_not_overflow_quietly() {
+        // Every row of splitters doubles the timelines, 140 of them is past what a u128 holds.
+        let width = 300;
+        let mut wide = format!("{}S{}\n", ".".repeat(width / 2), ".".repeat(width / 2));
+        for _ in 0..140 {
+            wide.push_str(&"^".repeat(width + 1));
+            wide.push('\n');
+            wide.push_str(&".".repeat(width + 1));
+            wide.push('\n');
+        }
+        let result = Manifold::try_from(wide.as_str()).unwrap().propagate();
+        assert!(matches!(result, Err(ManifoldError::TooManyTimelines { .. })));
+    }
+
+    #[test]
+    fn test_bad_manifolds() {
+        assert_eq!(Manifold::try_from("...\n.^.\n").unwrap_err(), ManifoldError::NoStart);
+        assert_eq!(
+            Manifold::try_from("S..\n..S\n").unwrap_err(),
+            ManifoldError::MultipleStarts { first: (0, 0), second: (1, 2) }
+        );
+        assert_eq!(
+            Manifold::try_from(".S.\n..\n").unwrap_err(),
+            ManifoldError::RaggedRow { row: 1, width: 2, expected: 3 }
+        );
+        assert_eq!(
+            Manifold::try_from(".S.\n.x.\n").unwrap_err(),
+            ManifoldError::UnknownCell { row: 1, col: 1, symbol: 'x' }
+        );
+    }
+}
@@ -0,0 +1,377 @@
+/* Union-Find (Disjoint Set), the one from this day grown up a bit so any day
+ * that needs "which things are connected" can pull it in with
+ * #[path = ...] mod disjoint_set; (2024 day18 does):
+ *
+ *  - DisjointSet works on dense indices 0..n, which is most days
+ *  - LabeledDisjointSet wraps that for when the things are names or positions
+ *  - RollbackDisjointSet skips path compression so unions can be undone, for
+ *    when you want to try a bunch of merges and then go back to how it was
+ *
+ * find is a loop and not recursion, a long enough chain of unions before the
+ * first find used to blow the stack.
+ */
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parent: Vec<usize>,
+    // Only up to date for roots.
+    size: Vec<usize>,
+    components: usize,
+}
+
+impl DisjointSet {
+    pub fn new(n: usize) -> DisjointSet {
+        DisjointSet {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            components: n,
+        }
+    }
+
+    /// How many elements, not how many groups.
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// A brand new element in a group of its own, returns its index.
+    pub fn add(&mut self) -> usize {
+        let x = self.parent.len();
+        self.parent.push(x);
+        self.size.push(1);
+        self.components += 1;
+        x
+    }
+
+    pub fn find(&mut self, x: usize) -> usize {
+        let mut root = x;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        // Second pass points everything on the way straight at the root.
+        let mut x = x;
+        while self.parent[x] != root {
+            let next = self.parent[x];
+            self.parent[x] = root;
+            x = next;
+        }
+        root
+    }
+
+    /// False if they were already together.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let mut a = self.find(a);
+        let mut b = self.find(b);
+        if a == b {
+            return false;
+        }
+
+        // union by size
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.components -= 1;
+        true
+    }
+
+    pub fn same(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// How big the group x is in.
+    pub fn size_of(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// How many separate groups there are right now.
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// Every root with the size of its group.
+    pub fn sizes(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.parent.len())
+            .filter(|&x| self.parent[x] == x)
+            .map(|root| (root, self.size[root]))
+    }
+
+    /// The k biggest group sizes, biggest first. A min heap of k keeps this
+    /// O(n log k) instead of sorting every group.
+    pub fn top_k_sizes(&self, k: usize) -> Vec<usize> {
+        let mut heap = std::collections::BinaryHeap::with_capacity(k + 1);
+        for (_, size) in self.sizes() {
+            heap.push(std::cmp::Reverse(size));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec().into_iter().map(|std::cmp::Reverse(size)| size).collect()
+    }
+
+    /// Every group's members, in order of their smallest member, each one sorted.
+    pub fn components(&mut self) -> Vec<Vec<usize>> {
+        let mut slot_for_root = vec![usize::MAX; self.parent.len()];
+        let mut groups: Vec<Vec<usize>> = Vec::with_capacity(self.components);
+        for x in 0..self.parent.len() {
+            let root = self.find(x);
+            if slot_for_root[root] == usize::MAX {
+                slot_for_root[root] = groups.len();
+                groups.push(Vec::with_capacity(self.size[root]));
+            }
+            groups[slot_for_root[root]].push(x);
+        }
+        groups
+    }
+}
+
+/// A DisjointSet over anything hashable. Labels get added the first time
+/// they're seen, so union("a", "b") is fine without inserting either first.
+#[derive(Debug, Clone)]
+pub struct LabeledDisjointSet<L> {
+    index: HashMap<L, usize>,
+    labels: Vec<L>,
+    set: DisjointSet,
+}
+
+impl<L: Hash + Eq + Clone> Default for LabeledDisjointSet<L> {
+    fn default() -> Self {
+        LabeledDisjointSet::new()
+    }
+}
+
+impl<L: Hash + Eq + Clone> LabeledDisjointSet<L> {
+    pub fn new() -> LabeledDisjointSet<L> {
+        LabeledDisjointSet {
+            index: HashMap::new(),
+            labels: Vec::new(),
+            set: DisjointSet::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// The dense index for a label, adding it in a group of its own if it's new.
+    pub fn insert(&mut self, label: L) -> usize {
+        if let Some(&x) = self.index.get(&label) {
+            return x;
+        }
+        let x = self.set.add();
+        self.index.insert(label.clone(), x);
+        self.labels.push(label);
+        x
+    }
+
+    /// The label standing in for the whole group, None if it's never been seen.
+    pub fn find(&mut self, label: &L) -> Option<&L> {
+        let x = *self.index.get(label)?;
+        let root = self.set.find(x);
+        Some(&self.labels[root])
+    }
+
+    pub fn union(&mut self, a: L, b: L) -> bool {
+        let a = self.insert(a);
+        let b = self.insert(b);
+        self.set.union(a, b)
+    }
+
+    pub fn same(&mut self, a: &L, b: &L) -> bool {
+        match (self.index.get(a), self.index.get(b)) {
+            (Some(&a), Some(&b)) => self.set.same(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn size_of(&mut self, label: &L) -> usize {
+        self.index.get(label).map_or(0, |&x| self.set.size_of(x))
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.set.component_count()
+    }
+
+    pub fn top_k_sizes(&self, k: usize) -> Vec<usize> {
+        self.set.top_k_sizes(k)
+    }
+
+    /// Every group, in the order their first label was inserted.
+    pub fn components(&mut self) -> Vec<Vec<L>> {
+        self.set
+            .components()
+            .into_iter()
+            .map(|group| group.into_iter().map(|x| self.labels[x].clone()).collect())
+            .collect()
+    }
+}
+
+/* Path compression rewrites parents on every find, which makes undoing a union
+ * impossible. Union by size alone still keeps the trees O(log n) deep, so
+ * finds stay cheap, and undoing is just putting back the one parent and size
+ * that union changed.
+ */
+#[derive(Debug, Clone)]
+pub struct RollbackDisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    components: usize,
+    // The root that got attached by each successful union, so it can be detached.
+    history: Vec<usize>,
+}
+
+impl RollbackDisjointSet {
+    pub fn new(n: usize) -> RollbackDisjointSet {
+        RollbackDisjointSet {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            components: n,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn find(&self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            x = self.parent[x];
+        }
+        x
+    }
+
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let mut a = self.find(a);
+        let mut b = self.find(b);
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.components -= 1;
+        self.history.push(b);
+        true
+    }
+
+    pub fn same(&self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    pub fn size_of(&self, x: usize) -> usize {
+        self.size[self.find(x)]
+    }
+
+    pub fn component_count(&self) -> usize {
+        self.components
+    }
+
+    /// Something to hand back to rollback() later.
+    pub fn snapshot(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Undo the last successful union, false if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        let Some(b) = self.history.pop() else {
+            return false;
+        };
+        let a = self.parent[b];
+        self.parent[b] = b;
+        self.size[a] -= self.size[b];
+        self.components += 1;
+        true
+    }
+
+    /// Undo every union made since the snapshot was taken.
+    pub fn rollback(&mut self, snapshot: usize) {
+        while self.history.len() > snapshot {
+            self.undo();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_union_find_and_components() {
+        let mut set = DisjointSet::new(7);
+        assert!(set.union(0, 1));
+        assert!(set.union(1, 2));
+        assert!(set.union(5, 3));
+        assert!(!set.union(2, 0));
+        assert_eq!(set.component_count(), 4);
+        assert!(set.same(0, 2));
+        assert!(!set.same(0, 3));
+        assert_eq!(set.size_of(2), 3);
+        assert_eq!(set.top_k_sizes(2), vec![3, 2]);
+        assert_eq!(set.top_k_sizes(10), vec![3, 2, 1, 1]);
+        assert_eq!(set.components(), vec![vec![0, 1, 2], vec![3, 5], vec![4], vec![6]]);
+
+        let x = set.add();
+        assert_eq!(x, 7);
+        set.union(x, 6);
+        assert_eq!(set.component_count(), 4);
+    }
+
+    #[test]
+    fn test_long_chain_does_not_blow_the_stack() {
+        // Union by size would flatten this, so build the chain by hand.
+        let n = 1_000_000;
+        let mut set = DisjointSet::new(n);
+        for x in 1..n {
+            set.parent[x] = x - 1;
+        }
+        assert_eq!(set.find(n - 1), 0);
+        assert_eq!(set.parent[n - 1], 0);
+    }
+
+    #[test]
+    fn test_labels() {
+        let mut set = LabeledDisjointSet::new();
+        set.union("kh", "tc");
+        set.union("qp", "kh");
+        set.insert("de");
+        assert_eq!(set.len(), 4);
+        assert_eq!(set.component_count(), 2);
+        assert!(set.same(&"tc", &"qp"));
+        assert!(!set.same(&"tc", &"nope"));
+        assert_eq!(set.size_of(&"qp"), 3);
+        assert_eq!(set.find(&"nope"), None);
+        assert_eq!(set.components(), vec![vec!["kh", "tc", "qp"], vec!["de"]]);
+    }
+
+    #[test]
+    fn test_rollback() {
+        let mut set = RollbackDisjointSet::new(5);
+        set.union(0, 1);
+        let snapshot = set.snapshot();
+        set.union(2, 3);
+        set.union(1, 3);
+        assert!(!set.union(0, 2));
+        assert_eq!(set.component_count(), 2);
+        assert_eq!(set.size_of(0), 4);
+
+        set.rollback(snapshot);
+        assert_eq!(set.component_count(), 4);
+        assert!(set.same(0, 1));
+        assert!(!set.same(1, 3));
+        assert_eq!(set.size_of(3), 1);
+        assert!(set.undo());
+        assert!(!set.undo());
+        assert_eq!(set.component_count(), 5);
+    }
+}
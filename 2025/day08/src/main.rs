@@ -1,83 +1,55 @@
 use std::fs;
 use std::time::Instant;
-use std::collections::{HashSet, HashMap};
+
+// Shared with 2024 day18, which uses the other parts of it.
+#[allow(dead_code)]
+mod disjoint_set;
+mod spatial;
+use disjoint_set::DisjointSet;
+use spatial::ClosestPairs;
 
 // This is the trick: https://en.wikipedia.org/wiki/Kruskal%27s_algorithm
 fn main() {
     let raw_data = fs::read_to_string("./input").expect("bad input data");
-    let raw_data = raw_data.as_str();
+    let points = parse_points(raw_data.as_str());
     let i = Instant::now();
-    p1(raw_data);
+    p1(&points, 1000);
     println!("Took: {:?}", i.elapsed());
 
     let i = Instant::now();
-    p2(raw_data);
+    p2(&points);
     println!("Took: {:?}", i.elapsed());
 }
 
 type PointType = i128;
-type Tuple3 = (PointType, PointType, PointType); 
+type Tuple3 = (PointType, PointType, PointType);
 
-fn p1(raw_data: &str) {
-    let num_connections = 1000;
-    let points: Vec<Tuple3> = raw_data.lines().take_while(|line| !line.is_empty()).map(|line| {
+fn parse_points(raw_data: &str) -> Vec<Tuple3> {
+    raw_data.lines().take_while(|line| !line.is_empty()).map(|line| {
         let mut iter = line.split(",");
         (
             iter.next().expect("Option not defined x").parse::<PointType>().expect("Could not parse number x"),
             iter.next().expect("Option not defined y").parse::<PointType>().expect("Could not parse number y"),
             iter.next().expect("Option not defined z").parse::<PointType>().expect("Could not parse number z")
         )
-    }).collect();
-    
-    let mut index = HashMap::<Tuple3, usize>::new();
-    for (i, p) in points.iter().enumerate() {
-        index.insert(*p, i);
-    }
-
-    // Treat tuple connections as graph edges
-    let mut edges = vec![];
-    for (i, a) in points.iter().enumerate() {
-        for (j, b) in points.iter().enumerate().skip(i + 1) {
-            edges.push((euclidean_distance_squared(a, b), i, j));
-        }
-    }
-
-    // And sort it all by distance
-    edges.sort_by(|a, b| {
-        a.0.cmp(&b.0)
-            .then_with(|| points[a.1].cmp(&points[b.1]))
-            .then_with(|| points[a.2].cmp(&points[b.2]))
-    });
-
-    // The DSU starts with a single point.
-    // then we start adding in each edge
-    let mut dsu = DSU::new(points.len());
-    let mut attempts = 0;
-    for (_, i, j) in edges {
-        if attempts == num_connections {
-            break;
-        }
-        attempts += 1;
-        dsu.union(i, j);
-    }
-
-
-    // count each circuit's size
-    let mut sizes = Vec::new();
-    let mut seen = HashSet::new();
+    }).collect()
+}
 
-    for i in 0..points.len() {
-        let root = dsu.find(i);
-        if seen.insert(root) {
-            sizes.push(dsu.size[root]);
-        }
+fn p1(points: &[Tuple3], num_connections: usize) -> usize {
+    // The disjoint set starts with every point on its own.
+    // then we start adding in each edge, shortest first, straight off the index
+    // rather than sorting every pair up front.
+    let mut circuits = DisjointSet::new(points.len());
+    for (_, i, j) in ClosestPairs::new(points).take(num_connections) {
+        circuits.union(i, j);
     }
 
-    sizes.sort_by(|a, b| b.cmp(a));
-    let result = sizes[0] * sizes[1] * sizes[2];
+    let sizes = circuits.top_k_sizes(3);
+    let result = sizes.iter().product();
 
-    println!("Largest 3 circuits: {:?}", &sizes[..3]);
+    println!("Largest 3 circuits: {:?}", sizes);
     println!("Product: {}", result);
+    result
 }
 
 // for sorting purposes, squared and not squared work just fine.
@@ -87,105 +59,33 @@ fn euclidean_distance_squared(p1: &Tuple3, p2: &Tuple3) -> PointType {
     (p1.2 - p2.2) * (p1.2 - p2.2)
 }
 
-// Fancy little thing called a Disjoint Set
-#[derive(Debug)]
-struct DSU {
-    parent: Vec<usize>,
-    size: Vec<usize>,
-}
-
-/**
- * Union-Find (Disjoint Set) Data Structure
- * 
- * Tracks which elements belong to the same group (circuit).
- * 
- * - All elements in the same set have the same root
- * - Path compression: flattens trees during traversal for speed
- * - Union by rank: always attaches smaller tree to larger tree
- */
-impl DSU {
-    fn new(n: usize) -> Self {
-        DSU {
-            parent: (0..n).collect(),
-            size: vec![1; n],
-        }
-    }
-
-    fn find(&mut self, x: usize) -> usize {
-        if self.parent[x] != x {
-            self.parent[x] = self.find(self.parent[x]); // path compression
-        }
-        self.parent[x]
-    }
-
-    fn union(&mut self, a: usize, b: usize) -> bool {
-        let mut a = self.find(a);
-        let mut b = self.find(b);
-
-        if a == b {
-            return false;
-        }
-
-        // union by size
-        if self.size[a] < self.size[b] {
-            std::mem::swap(&mut a, &mut b);
-        }
-
-        self.parent[b] = a;
-        self.size[a] += self.size[b];
-        true
-    }
-}
-
-
-fn p2(raw_data: &str) {
-    let points: Vec<Tuple3> = raw_data.lines().take_while(|line| !line.is_empty()).map(|line| {
-        let mut iter = line.split(",");
-        (
-            iter.next().expect("Option not defined x").parse::<PointType>().expect("Could not parse number x"),
-            iter.next().expect("Option not defined y").parse::<PointType>().expect("Could not parse number y"),
-            iter.next().expect("Option not defined z").parse::<PointType>().expect("Could not parse number z")
-        )
-    }).collect();
-    
-    // Step 1: map points to indices
-    let mut index = HashMap::<Tuple3, usize>::new();
-    for (i, p) in points.iter().enumerate() {
-        index.insert(*p, i);
-    }
-
-    // Treat tuple connections as graph edges
-    let mut edges = vec![];
-    for (i, a) in points.iter().enumerate() {
-        for (j, b) in points.iter().enumerate().skip(i + 1) {
-            edges.push((euclidean_distance_squared(a, b), i, j));
-        }
-    }
-
-    // And sort it all by distance
-    edges.sort_by(|a, b| {
-        a.0.cmp(&b.0)
-            .then_with(|| points[a.1].cmp(&points[b.1]))
-            .then_with(|| points[a.2].cmp(&points[b.2]))
-    });
-
-    // The DSU starts with a single point.
-    // then we start adding in each edge
-    let mut dsu = DSU::new(points.len());
-    let mut merges = 0;
+fn p2(points: &[Tuple3]) -> PointType {
+    // Keep connecting until there's one circuit, the pair that did it is the answer.
+    let mut circuits = DisjointSet::new(points.len());
     let mut final_merge: Option<(Tuple3, Tuple3)> = None;
-    for (_, i, j) in edges {
-        let merged = dsu.union(i, j);
-        if merged {
-            merges += 1;
-        }
-        if merges == points.len() -1 {
+    for (_, i, j) in ClosestPairs::new(points) {
+        if circuits.union(i, j) && circuits.component_count() == 1 {
             final_merge = Some((points[i], points[j]));
             break;
         }
     }
 
     let final_merge = final_merge.expect("Didnt compute final merge?");
-    println!("final merge X {:?}", final_merge.0.0 * final_merge.1.0);
+    let result = final_merge.0.0 * final_merge.1.0;
+    println!("final merge X {:?}", result);
+    result
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "162,817,812\n57,618,57\n906,360,560\n592,479,940\n352,342,300\n466,668,158\n542,29,236\n431,825,988\n739,650,466\n52,470,668\n216,146,977\n819,987,18\n117,168,530\n805,96,715\n346,949,466\n970,615,88\n941,993,340\n862,61,35\n984,92,344\n425,690,689\n";
+
+    #[test]
+    fn test_example() {
+        let points = parse_points(EXAMPLE);
+        assert_eq!(p1(&points, 10), 40);
+        assert_eq!(p2(&points), 25272);
+    }
+}
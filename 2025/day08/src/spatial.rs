@@ -0,0 +1,221 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{PointType, Tuple3, euclidean_distance_squared};
+
+/* Sorting every pair of junction boxes is n² memory before the first
+ * connection gets made, which is fine for 1000 boxes and hopeless for 50000.
+ * Kruskal only ever wants the next shortest pair though, so:
+ *
+ *  - a k-d tree over the points answers "k nearest neighbours of box i"
+ *  - every box keeps a little buffer of its nearest neighbours, only looking at
+ *    boxes with a bigger index so each pair belongs to exactly one box
+ *  - a heap holds each box's next unused neighbour, pop the smallest, refill
+ *    that box from its buffer, and when a buffer runs dry ask the tree again
+ *    for twice as many.
+ *
+ * Ties are broken by the coordinates of the two boxes, same as the old sort,
+ * so the stream comes out in exactly the order sorting everything would give.
+ */
+
+// Ordering for one box's neighbours: distance, then where the neighbour is, then
+// its index in case two boxes sit on the same spot.
+type Neighbour = (PointType, Tuple3, usize);
+// And for the pairs: distance, both boxes' coordinates, then both indices.
+type Pair = (PointType, Tuple3, Tuple3, usize, usize);
+
+pub struct KdTree<'a> {
+    points: &'a [Tuple3],
+    // Indices into points, laid out so the middle of every slice is the node
+    // splitting it, on axis depth % 3.
+    order: Vec<usize>,
+}
+
+fn axis(point: &Tuple3, depth: usize) -> PointType {
+    match depth % 3 {
+        0 => point.0,
+        1 => point.1,
+        _ => point.2,
+    }
+}
+
+impl<'a> KdTree<'a> {
+    pub fn new(points: &'a [Tuple3]) -> KdTree<'a> {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        build(points, &mut order, 0);
+        KdTree { points, order }
+    }
+
+    /// The `k` closest points to `points[query]` that pass `keep`, closest first.
+    pub fn nearest(&self, query: usize, k: usize, keep: impl Fn(usize) -> bool) -> Vec<(PointType, usize)> {
+        let mut best: BinaryHeap<Neighbour> = BinaryHeap::with_capacity(k + 1);
+        if k > 0 {
+            self.search(&self.order, 0, &self.points[query], k, &keep, &mut best);
+        }
+        best.into_sorted_vec().into_iter().map(|(d, _, j)| (d, j)).collect()
+    }
+
+    fn search(
+        &self,
+        slice: &[usize],
+        depth: usize,
+        target: &Tuple3,
+        k: usize,
+        keep: &impl Fn(usize) -> bool,
+        best: &mut BinaryHeap<Neighbour>,
+    ) {
+        if slice.is_empty() {
+            return;
+        }
+        let mid = slice.len() / 2;
+        let node = slice[mid];
+        let point = &self.points[node];
+
+        if keep(node) {
+            best.push((euclidean_distance_squared(target, point), *point, node));
+            if best.len() > k {
+                best.pop();
+            }
+        }
+
+        let diff = axis(target, depth) - axis(point, depth);
+        let (near, far) = if diff < 0 {
+            (&slice[..mid], &slice[mid + 1..])
+        } else {
+            (&slice[mid + 1..], &slice[..mid])
+        };
+        self.search(near, depth + 1, target, k, keep, best);
+        // Anything on the far side is at least diff² away. <= and not < since a
+        // tie on distance can still win on the coordinates.
+        if best.len() < k || best.peek().is_some_and(|&(worst, _, _)| diff * diff <= worst) {
+            self.search(far, depth + 1, target, k, keep, best);
+        }
+    }
+}
+
+fn build(points: &[Tuple3], slice: &mut [usize], depth: usize) {
+    if slice.len() <= 1 {
+        return;
+    }
+    let mid = slice.len() / 2;
+    slice.select_nth_unstable_by_key(mid, |&i| axis(&points[i], depth));
+    let (left, right) = slice.split_at_mut(mid);
+    build(points, left, depth + 1);
+    build(points, &mut right[1..], depth + 1);
+}
+
+struct NeighbourStream {
+    buffer: Vec<(PointType, usize)>,
+    next: usize,
+    // How many neighbours were asked for last time, fewer came back means that's all of them.
+    asked: usize,
+}
+
+/// Every pair of points exactly once, shortest first, worked out as it goes.
+pub struct ClosestPairs<'a> {
+    tree: KdTree<'a>,
+    streams: Vec<NeighbourStream>,
+    heap: BinaryHeap<Reverse<Pair>>,
+}
+
+impl<'a> ClosestPairs<'a> {
+    pub fn new(points: &'a [Tuple3]) -> ClosestPairs<'a> {
+        let mut pairs = ClosestPairs {
+            tree: KdTree::new(points),
+            streams: (0..points.len())
+                .map(|_| NeighbourStream { buffer: Vec::new(), next: 0, asked: 0 })
+                .collect(),
+            heap: BinaryHeap::with_capacity(points.len()),
+        };
+        for i in 0..points.len() {
+            pairs.refill(i);
+        }
+        pairs
+    }
+
+    // Push the next neighbour of point i onto the heap, if it has one left.
+    fn refill(&mut self, i: usize) {
+        let stream = &mut self.streams[i];
+        if stream.next == stream.buffer.len() {
+            if stream.buffer.len() < stream.asked {
+                return;
+            }
+            stream.asked = (stream.asked * 2).max(4);
+            // Same ordering every time, so the first `next` are the ones already handed out.
+            stream.buffer = self.tree.nearest(i, stream.asked, |j| j > i);
+            if stream.next == stream.buffer.len() {
+                return;
+            }
+        }
+        let (d, j) = stream.buffer[stream.next];
+        stream.next += 1;
+        let points = self.tree.points;
+        self.heap.push(Reverse((d, points[i], points[j], i, j)));
+    }
+}
+
+impl Iterator for ClosestPairs<'_> {
+    type Item = (PointType, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Reverse((d, _, _, i, j)) = self.heap.pop()?;
+        self.refill(i);
+        Some((d, i, j))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A little xorshift so the test doesn't need the rand crate.
+    fn scatter(n: usize, spread: PointType) -> Vec<Tuple3> {
+        let mut state: u64 = 0x2545F4914F6CDD1D;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % spread as u64) as PointType
+        };
+        (0..n).map(|_| (next(), next(), next())).collect()
+    }
+
+    fn sorted_pairs(points: &[Tuple3]) -> Vec<(PointType, usize, usize)> {
+        let mut edges = vec![];
+        for (i, a) in points.iter().enumerate() {
+            for (j, b) in points.iter().enumerate().skip(i + 1) {
+                edges.push((euclidean_distance_squared(a, b), i, j));
+            }
+        }
+        edges.sort_by(|a, b| {
+            a.0.cmp(&b.0)
+                .then_with(|| points[a.1].cmp(&points[b.1]))
+                .then_with(|| points[a.2].cmp(&points[b.2]))
+        });
+        edges
+    }
+
+    #[test]
+    fn test_matches_sorting_everything() {
+        // Small spread on purpose so there are plenty of ties and repeated points.
+        for (n, spread) in [(0, 10), (1, 10), (2, 10), (60, 5), (300, 1000)] {
+            let points = scatter(n, spread);
+            let streamed: Vec<_> = ClosestPairs::new(&points).collect();
+            let expected = sorted_pairs(&points);
+            assert_eq!(streamed.len(), expected.len());
+            for (got, want) in streamed.iter().zip(&expected) {
+                // Two copies of the same point can come out either way round.
+                assert_eq!(got.0, want.0);
+                assert_eq!((points[got.1], points[got.2]), (points[want.1], points[want.2]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_nearest() {
+        let points = vec![(0, 0, 0), (10, 0, 0), (1, 1, 1), (0, 0, 3), (-2, 0, 0)];
+        let tree = KdTree::new(&points);
+        assert_eq!(tree.nearest(0, 3, |j| j != 0), vec![(3, 2), (4, 4), (9, 3)]);
+        assert_eq!(tree.nearest(0, 10, |j| j > 2), vec![(4, 4), (9, 3)]);
+    }
+}
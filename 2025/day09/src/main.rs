@@ -1,7 +1,8 @@
 use std::fs;
 use std::time::Instant;
-use std::collections::HashSet;
-use std::cmp::{min, max};
+
+mod polygon;
+use polygon::{PolygonError, RectilinearPolygon};
 
 fn main() {
     let raw_data = fs::read_to_string("./input").expect("bad input data");
@@ -15,7 +16,10 @@ fn main() {
     let i = Instant::now();
     let result = p2(raw_data);
     let time = i.elapsed();
-    println!("{:?}", result);
+    match result {
+        Ok(area) => println!("{:?}", area),
+        Err(problem) => println!("red tiles don't make a proper loop: {:?}", problem),
+    }
     println!("Took: {:?}", time);
 }
 
@@ -43,7 +47,7 @@ fn p1(raw_data: &str) -> ResultType {
     *areas.iter().rev().take(1).next().expect("No answer")
 }
 
-fn p2(raw_data: &str) -> ResultType {
+fn p2(raw_data: &str) -> Result<ResultType, PolygonError> {
     let red_tiles: Vec<(ResultType, ResultType)> = raw_data.lines().take_while(|line| !line.is_empty()).map(|line| {
         let mut xy = line.split(",");
         (
@@ -55,9 +59,15 @@ fn p2(raw_data: &str) -> ResultType {
 }
 
 
+/* point_in_poly and adjacent_edge_intersects were how part 2 used to check
+ * every rectangle, O(n³) all told. RectilinearPolygon does it in O(1) per
+ * rectangle now, these stick around to check it against.
+ */
+
 /// Returns true if (x,y) is inside the polygon using the even–odd rule.
 /// The given x y is the scanline
 /// similar to stuff like https://wrfranklin.org/Research/Short_Notes/pnpoly.html
+#[cfg(test)]
 pub fn point_in_poly(x: ResultType, y: ResultType, poly: &[(ResultType,ResultType)]) -> bool {
     let mut inside = false;
     let mut j = poly.len() - 1;
@@ -90,6 +100,7 @@ pub fn point_in_poly(x: ResultType, y: ResultType, poly: &[(ResultType,ResultTyp
 /// edge crosses into the *interior* of the rectangle.
 ///
 /// https://stackoverflow.com/questions/3235385/given-a-bounding-box-and-a-line-two-points-determine-if-the-line-intersects-t
+#[cfg(test)]
 pub fn adjacent_edge_intersects(
     rect: ((ResultType,ResultType),(ResultType,ResultType)),
     edge: ((ResultType,ResultType),(ResultType,ResultType)),
@@ -134,55 +145,12 @@ pub fn rectangle_area(p1: (ResultType,ResultType), p2: (ResultType,ResultType))
     (1 + (p1.0 - p2.0).abs()) * (1 + (p1.1 - p2.1).abs())
 }
 
-pub fn largest_valid_rectangle(red_tiles: &[(ResultType,ResultType)]) -> ResultType {
-    let mut max_area = 0;
-    for p1 in red_tiles {
-        for  p2 in red_tiles {
-            if p1 == p2 {
-                continue;
-            }
-
-            let left   = min(p1.0, p2.0);
-            let right  = max(p1.0, p2.0);
-            let top    = min(p1.1, p2.1);
-            let bottom = max(p1.1, p2.1);
-
-            // are the corners inside of the polygon?
-            if !(
-                point_in_poly(left, top, red_tiles) &&
-                point_in_poly(left, bottom, red_tiles) &&
-                point_in_poly(right, top, red_tiles) &&
-                point_in_poly(right, bottom, red_tiles)
-            ) {
-                continue;
-            }
-
-
-            // 
-            let mut blocked = false;
-            for i in 0..red_tiles.len() {
-                let a = red_tiles[i];
-                let b = red_tiles[(i+1) % red_tiles.len()];
-                if adjacent_edge_intersects(( (left, top), (right, bottom) ), (a,b)) {
-                    blocked = true;
-                    break;
-                }
-            }
-            if blocked { 
-                continue; 
-            }
-
-            let area = (1 + (p1.0 - p2.0).abs()) * (1 + (p1.1 - p2.1).abs());
-            if area > max_area {
-                max_area = area;
-            }
-        }
-    }
-
-    max_area
+/// Err if the red tiles don't make a proper loop.
+pub fn largest_valid_rectangle(red_tiles: &[(ResultType,ResultType)]) -> Result<ResultType, PolygonError> {
+    let polygon = RectilinearPolygon::try_from(red_tiles)?;
+    Ok(polygon.largest_inscribed_rectangle().map_or(0, |(area, _, _)| area))
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,6 +264,78 @@ mod tests {
 
         let area = largest_valid_rectangle(&poly);
 
-        assert_eq!(area, 24);
+        assert_eq!(area, Ok(24));
+    }
+
+    #[test]
+    fn test_not_a_loop() {
+        // Too few corners, and a diagonal edge.
+        assert_eq!(largest_valid_rectangle(&[(0,0), (4,0), (4,4)]), Err(PolygonError::TooFewCorners(3)));
+        // An odd number of axis aligned edges can't take turns being across
+        // and down, so somewhere two in a row go the same way.
+        let odd = vec![(0,0), (2,0), (4,0), (4,4), (0,4)];
+        assert_eq!(largest_valid_rectangle(&odd), Err(PolygonError::NotATurn(0)));
+        let diagonal = vec![(0,0), (4,0), (4,4), (1,5), (0,4)];
+        assert!(matches!(largest_valid_rectangle(&diagonal), Err(PolygonError::NotAxisAligned(_))));
+    }
+
+    // The old way of doing part 2, kept as something to check against.
+    fn largest_valid_rectangle_brute(red_tiles: &[(ResultType,ResultType)]) -> ResultType {
+        let mut max_area = 0;
+        for p1 in red_tiles {
+            for  p2 in red_tiles {
+                if p1 == p2 {
+                    continue;
+                }
+
+                let left   = p1.0.min(p2.0);
+                let right  = p1.0.max(p2.0);
+                let top    = p1.1.min(p2.1);
+                let bottom = p1.1.max(p2.1);
+
+                // are the corners inside of the polygon?
+                if !(
+                    point_in_poly(left, top, red_tiles) &&
+                    point_in_poly(left, bottom, red_tiles) &&
+                    point_in_poly(right, top, red_tiles) &&
+                    point_in_poly(right, bottom, red_tiles)
+                ) {
+                    continue;
+                }
+
+
+                // 
+                let mut blocked = false;
+                for i in 0..red_tiles.len() {
+                    let a = red_tiles[i];
+                    let b = red_tiles[(i+1) % red_tiles.len()];
+                    if adjacent_edge_intersects(( (left, top), (right, bottom) ), (a,b)) {
+                        blocked = true;
+                        break;
+                    }
+                }
+                if blocked { 
+                    continue; 
+                }
+
+                let area = (1 + (p1.0 - p2.0).abs()) * (1 + (p1.1 - p2.1).abs());
+                if area > max_area {
+                    max_area = area;
+                }
+            }
+        }
+
+        max_area
+    }
+
+    #[test]
+    fn test_matches_the_old_way() {
+        // A staircase with a couple of notches cut into it.
+        let poly = vec![
+            (0,0),(20,0),(20,4),(14,4),(14,2),(10,2),(10,9),(30,9),(30,15),(6,15),(6,11),(3,11),(3,6),(0,6)
+        ];
+        assert_eq!(largest_valid_rectangle(&poly), Ok(largest_valid_rectangle_brute(&poly)));
+        let example = vec![(7,1),(11,1),(11,7),(9,7),(9,5),(2,5),(2,3),(7,3)];
+        assert_eq!(largest_valid_rectangle(&example), Ok(largest_valid_rectangle_brute(&example)));
     }
 }
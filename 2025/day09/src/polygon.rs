@@ -0,0 +1,296 @@
+/* An axis aligned polygon on the tile grid, red tiles as the corners, the
+ * tiles along the edges and everything enclosed by them counting as green.
+ *
+ * Checking a rectangle used to mean point-in-polygon on its corners and then
+ * every edge against it. Instead squish the grid down: every x a corner sits
+ * on gets its own column, and each run of x's between two corners gets one
+ * column too, since nothing changes inside a run. Same for y. Then each
+ * squished cell is either all green or all not, and a 2D prefix sum over the
+ * "not green" cells answers "is this whole rectangle green" in O(1).
+ *
+ *   x:  2 | 3..6 | 7 | 8 | 9 | 10 | 11
+ */
+
+use crate::ResultType;
+
+#[derive(Debug, PartialEq)]
+pub enum PolygonError {
+    TooFewCorners(usize),
+    // The edge from corner i to i + 1 isn't horizontal or vertical, or has no length.
+    NotAxisAligned(usize),
+    // Edges i and i + 1 go the same way, so corner i + 1 isn't really a corner.
+    NotATurn(usize),
+    EdgesCross(usize, usize),
+}
+
+type Point = (ResultType, ResultType);
+
+#[derive(Debug)]
+pub struct RectilinearPolygon {
+    corners: Vec<Point>,
+    // Squished coordinates, each is the first real coordinate of its run.
+    xs: Vec<ResultType>,
+    ys: Vec<ResultType>,
+    // Only on_boundary looks at this.
+    #[allow(dead_code)]
+    boundary: Vec<Vec<bool>>,
+    // outside_before[r][c] is how many squished cells above and left of (r, c) aren't green.
+    outside_before: Vec<Vec<u32>>,
+}
+
+// Every corner coordinate, plus the start of the gap after it if there is one.
+fn squish(mut values: Vec<ResultType>) -> Vec<ResultType> {
+    values.sort_unstable();
+    values.dedup();
+    let mut squished = Vec::with_capacity(values.len() * 2);
+    for (i, &v) in values.iter().enumerate() {
+        squished.push(v);
+        if values.get(i + 1).is_some_and(|&next| next > v + 1) {
+            squished.push(v + 1);
+        }
+    }
+    squished
+}
+
+// Which squished index a real coordinate falls into, None if outside all of them.
+fn squished_index(squished: &[ResultType], value: ResultType) -> Option<usize> {
+    if squished.first().is_none_or(|&first| value < first) || squished.last().is_none_or(|&last| value > last) {
+        return None;
+    }
+    Some(squished.partition_point(|&v| v <= value) - 1)
+}
+
+impl TryFrom<&[Point]> for RectilinearPolygon {
+    type Error = PolygonError;
+
+    fn try_from(corners: &[Point]) -> Result<RectilinearPolygon, PolygonError> {
+        let n = corners.len();
+        if n < 4 {
+            return Err(PolygonError::TooFewCorners(n));
+        }
+        let edge = |i: usize| (corners[i], corners[(i + 1) % n]);
+        let is_vertical = |i: usize| edge(i).0.0 == edge(i).1.0;
+
+        for i in 0..n {
+            let ((ax, ay), (bx, by)) = edge(i);
+            if (ax == bx) == (ay == by) {
+                return Err(PolygonError::NotAxisAligned(i));
+            }
+        }
+        for i in 0..n {
+            if is_vertical(i) == is_vertical((i + 1) % n) {
+                return Err(PolygonError::NotATurn(i));
+            }
+        }
+
+        // Edges next to each other share a corner, anything else touching at all
+        // means the outline crosses itself. Axis aligned segments are their own
+        // bounding boxes, so overlapping boxes is the whole test.
+        let bounds = |i: usize| {
+            let ((ax, ay), (bx, by)) = edge(i);
+            (ax.min(bx), ax.max(bx), ay.min(by), ay.max(by))
+        };
+        for i in 0..n {
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                let (a, b) = (bounds(i), bounds(j));
+                if a.0 <= b.1 && b.0 <= a.1 && a.2 <= b.3 && b.2 <= a.3 {
+                    return Err(PolygonError::EdgesCross(i, j));
+                }
+            }
+        }
+
+        let xs = squish(corners.iter().map(|&(x, _)| x).collect());
+        let ys = squish(corners.iter().map(|&(_, y)| y).collect());
+        let x_of = |x| squished_index(&xs, x).unwrap();
+        let y_of = |y| squished_index(&ys, y).unwrap();
+
+        let mut boundary = vec![vec![false; xs.len()]; ys.len()];
+        for i in 0..n {
+            let ((ax, ay), (bx, by)) = edge(i);
+            let (c0, c1) = (x_of(ax.min(bx)), x_of(ax.max(bx)));
+            let (r0, r1) = (y_of(ay.min(by)), y_of(ay.max(by)));
+            for row in &mut boundary[r0..=r1] {
+                row[c0..=c1].fill(true);
+            }
+        }
+
+        // Ray cast to the left from each cell, same even-odd rule as before but a
+        // whole squished row at a time. A vertical edge counts for row y when it
+        // spans y to y + 1, so a ray along a horizontal edge isn't double counted.
+        let vertical_edges: Vec<(usize, ResultType, ResultType)> = (0..n)
+            .filter(|&i| is_vertical(i))
+            .map(|i| {
+                let ((x, ay), (_, by)) = edge(i);
+                (x_of(x), ay.min(by), ay.max(by))
+            })
+            .collect();
+
+        let mut outside_before = vec![vec![0u32; xs.len() + 1]; ys.len() + 1];
+        for (r, &y) in ys.iter().enumerate() {
+            let mut crossings_at = vec![false; xs.len()];
+            for &(c, low, high) in &vertical_edges {
+                if low <= y && y < high {
+                    crossings_at[c] = !crossings_at[c];
+                }
+            }
+            let mut inside = false;
+            let mut outside_in_row = 0;
+            for c in 0..xs.len() {
+                if !inside && !boundary[r][c] {
+                    outside_in_row += 1;
+                }
+                if crossings_at[c] {
+                    inside = !inside;
+                }
+                outside_before[r + 1][c + 1] = outside_before[r][c + 1] + outside_in_row;
+            }
+        }
+
+        Ok(RectilinearPolygon { corners: corners.to_vec(), xs, ys, boundary, outside_before })
+    }
+}
+
+impl RectilinearPolygon {
+    fn cell(&self, (x, y): Point) -> Option<(usize, usize)> {
+        Some((squished_index(&self.ys, y)?, squished_index(&self.xs, x)?))
+    }
+
+    fn outside_in(&self, r0: usize, c0: usize, r1: usize, c1: usize) -> u32 {
+        let p = &self.outside_before;
+        p[r1 + 1][c1 + 1] + p[r0][c0] - p[r0][c1 + 1] - p[r1 + 1][c0]
+    }
+
+    /// Is every tile in the rectangle with these opposite corners red or green.
+    pub fn contains_rectangle(&self, a: Point, b: Point) -> bool {
+        let corner_a = self.cell((a.0.min(b.0), a.1.min(b.1)));
+        let corner_b = self.cell((a.0.max(b.0), a.1.max(b.1)));
+        match (corner_a, corner_b) {
+            (Some((r0, c0)), Some((r1, c1))) => self.outside_in(r0, c0, r1, c1) == 0,
+            _ => false,
+        }
+    }
+
+    /// The biggest rectangle with red tiles on two opposite corners that's all
+    /// red or green, and those two corners.
+    pub fn largest_inscribed_rectangle(&self) -> Option<(ResultType, Point, Point)> {
+        let mut best: Option<(ResultType, Point, Point)> = None;
+        for (i, &a) in self.corners.iter().enumerate() {
+            for &b in &self.corners[i + 1..] {
+                let area = crate::rectangle_area(a, b);
+                if best.is_some_and(|(best_area, _, _)| area <= best_area) {
+                    continue;
+                }
+                if self.contains_rectangle(a, b) {
+                    best = Some((area, a, b));
+                }
+            }
+        }
+        best
+    }
+}
+
+// Questions about the shape itself. Part 2 never asks them, only the tests do.
+#[allow(dead_code)]
+impl RectilinearPolygon {
+    /// Area of the outline through the middle of the corner tiles, shoelace formula.
+    pub fn area(&self) -> ResultType {
+        let n = self.corners.len();
+        let twice: ResultType = (0..n)
+            .map(|i| {
+                let ((ax, ay), (bx, by)) = (self.corners[i], self.corners[(i + 1) % n]);
+                ax * by - bx * ay
+            })
+            .sum();
+        twice.abs() / 2
+    }
+
+    pub fn perimeter(&self) -> ResultType {
+        let n = self.corners.len();
+        (0..n)
+            .map(|i| {
+                let ((ax, ay), (bx, by)) = (self.corners[i], self.corners[(i + 1) % n]);
+                (ax - bx).abs() + (ay - by).abs()
+            })
+            .sum()
+    }
+
+    /// Every red and green tile. Pick's theorem: inside + boundary = A + B/2 + 1.
+    pub fn tile_count(&self) -> ResultType {
+        self.area() + self.perimeter() / 2 + 1
+    }
+
+    pub fn on_boundary(&self, point: Point) -> bool {
+        self.cell(point).is_some_and(|(r, c)| self.boundary[r][c])
+    }
+
+    /// Red or green, so on the outline or inside it.
+    pub fn contains(&self, point: Point) -> bool {
+        self.cell(point).is_some_and(|(r, c)| self.outside_in(r, c, r, c) == 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> RectilinearPolygon {
+        let corners = vec![(7, 1), (11, 1), (11, 7), (9, 7), (9, 5), (2, 5), (2, 3), (7, 3)];
+        RectilinearPolygon::try_from(corners.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_measurements() {
+        let polygon = example();
+        assert_eq!(polygon.area(), 8 + 18 + 4);
+        assert_eq!(polygon.perimeter(), 4 + 6 + 2 + 2 + 7 + 2 + 5 + 2);
+        // Count them in the puzzle's picture, 46 of them.
+        assert_eq!(polygon.tile_count(), 46);
+    }
+
+    #[test]
+    fn test_points() {
+        let polygon = example();
+        assert!(polygon.on_boundary((7, 2)));
+        assert!(polygon.on_boundary((5, 5)));
+        assert!(!polygon.on_boundary((8, 2)));
+        assert!(polygon.contains((8, 2)));
+        assert!(polygon.contains((3, 4)));
+        assert!(!polygon.contains((3, 2)));
+        assert!(!polygon.contains((10, 8)));
+        assert!(!polygon.contains((0, 0)));
+        let tiles = (0..13).flat_map(|x| (0..9).map(move |y| (x, y))).filter(|&p| polygon.contains(p)).count();
+        assert_eq!(tiles as ResultType, polygon.tile_count());
+    }
+
+    #[test]
+    fn test_rectangles() {
+        let polygon = example();
+        assert!(polygon.contains_rectangle((9, 5), (2, 3)));
+        assert!(!polygon.contains_rectangle((2, 5), (11, 1)));
+        assert_eq!(polygon.largest_inscribed_rectangle(), Some((24, (9, 5), (2, 3))));
+    }
+
+    #[test]
+    fn test_gap_between_edges_is_still_tiles() {
+        // A U shape whose two arms are right next to each other, there's no
+        // tile between them so the rectangle across is fine.
+        let corners = vec![(0, 0), (3, 0), (3, 5), (2, 5), (2, 1), (1, 1), (1, 5), (0, 5)];
+        let polygon = RectilinearPolygon::try_from(corners.as_slice()).unwrap();
+        assert!(polygon.contains_rectangle((0, 5), (3, 0)));
+        assert_eq!(polygon.tile_count(), 24);
+    }
+
+    #[test]
+    fn test_bad_polygons() {
+        let check = |corners: Vec<Point>| RectilinearPolygon::try_from(corners.as_slice()).unwrap_err();
+        assert_eq!(check(vec![(0, 0), (1, 0), (1, 1)]), PolygonError::TooFewCorners(3));
+        assert_eq!(check(vec![(0, 0), (2, 0), (2, 2), (1, 3)]), PolygonError::NotAxisAligned(2));
+        assert_eq!(check(vec![(0, 0), (1, 0), (2, 0), (2, 2), (0, 2)]), PolygonError::NotATurn(0));
+        // A figure of eight.
+        let eight = vec![(0, 0), (4, 0), (4, 2), (1, 2), (1, 4), (3, 4), (3, 1), (0, 1)];
+        assert_eq!(check(eight), PolygonError::EdgesCross(2, 5));
+    }
+}
@@ -0,0 +1,495 @@
+use crate::linalg::{Field, Rational};
+
+/* A small integer program solver so that part 2 stops relying on "try every
+ * free variable up to 501 and hope". The shape of the problem is always:
+ *
+ *   minimize   c . x
+ *   subject to A x = b, x >= 0, x whole numbers (and optionally x <= upper)
+ *
+ * The LP relaxation is solved exactly with a tableau simplex over Rationals,
+ * which gives a lower bound for every node of the branch and bound search. If
+ * there's no answer we hand back a proof instead of an empty Vec: a branching
+ * tree where every leaf has a Farkas certificate showing its LP is infeasible.
+ *
+ * Branching only ever has to stop when every variable has an upper bound. With
+ * unbounded ones, 2x - 2y = 1 has an LP answer at every node however deep the
+ * branches go, so the search gives up past MOST_BRANCHES deep and says it
+ * couldn't decide rather than running forever.
+ */
+const MOST_BRANCHES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct IntegerProgram {
+    pub constraints: Vec<Vec<i64>>,
+    pub rhs: Vec<i64>,
+    pub objective: Vec<i64>,
+    pub upper_bounds: Vec<Option<i64>>,
+}
+
+/// An extra bound placed on a single variable while branching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    AtMost(usize, i64),
+    AtLeast(usize, i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Proof {
+    /* One multiplier per row (the original constraints first, then one for each
+     * bound in effect at this node, in order). Multiplying through gives a row
+     * whose coefficients are all <= 0 but whose right hand side is > 0, which
+     * no x >= 0 can satisfy.
+     */
+    Farkas(Vec<Rational>),
+    /* Every whole number value of the variable is either <= at or >= at + 1. */
+    Branch {
+        variable: usize,
+        at: i64,
+        below: Box<Proof>,
+        above: Box<Proof>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Optimal { value: i64, solution: Vec<i64> },
+    Infeasible(Proof),
+    Unbounded,
+    /// Branching got too deep before it could either find the best answer or
+    /// prove there isn't one.
+    Undecided,
+}
+
+enum Relaxation {
+    Solved { value: Rational, x: Vec<Rational> },
+    Infeasible(Vec<Rational>),
+    Unbounded,
+}
+
+impl IntegerProgram {
+    pub fn new(constraints: Vec<Vec<i64>>, rhs: Vec<i64>, objective: Vec<i64>) -> Self {
+        assert_eq!(constraints.len(), rhs.len(), "one right hand side per constraint");
+        assert!(constraints.iter().all(|row| row.len() == objective.len()), "ragged constraints");
+        let upper_bounds = vec![None; objective.len()];
+        IntegerProgram { constraints, rhs, objective, upper_bounds }
+    }
+
+    pub fn with_upper_bounds(mut self, upper_bounds: Vec<Option<i64>>) -> Self {
+        assert_eq!(upper_bounds.len(), self.objective.len());
+        self.upper_bounds = upper_bounds;
+        self
+    }
+
+    fn variables(&self) -> usize {
+        self.objective.len()
+    }
+
+    pub fn solve(&self) -> Outcome {
+        let mut initial: Vec<Bound> = self
+            .upper_bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(v, upper)| upper.map(|u| Bound::AtMost(v, u)))
+            .collect();
+
+        let mut search = Search { program: self, best: None, unbounded: false, cut_off: None };
+        let proof = search.branch(&mut initial, 0);
+
+        if search.unbounded {
+            return Outcome::Unbounded;
+        }
+        // Whatever got cut off can't beat the best answer if its LP bound doesn't.
+        let beaten = |best: i64| search.cut_off.is_none_or(|lowest: Rational| lowest.ceil() >= best as i128);
+        match (search.best, proof) {
+            (Some((value, solution)), _) if beaten(value) => Outcome::Optimal { value, solution },
+            (None, Some(proof)) => Outcome::Infeasible(proof),
+            _ => Outcome::Undecided,
+        }
+    }
+
+    /* Turn the program plus the branching bounds into a plain equality system.
+     * Each bound becomes its own row with a fresh slack column:
+     *   x_v + s = u   for AtMost
+     *   x_v - s = l   for AtLeast
+     */
+    fn standard_form(&self, bounds: &[Bound]) -> (Vec<Vec<Rational>>, Vec<Rational>, Vec<Rational>) {
+        let n = self.variables();
+        let width = n + bounds.len();
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+
+        for (row, &rhs) in self.constraints.iter().zip(&self.rhs) {
+            let mut expanded: Vec<Rational> = row.iter().map(|&v| Rational::from(v)).collect();
+            expanded.resize(width, Rational::zero());
+            a.push(expanded);
+            b.push(Rational::from(rhs));
+        }
+
+        for (i, bound) in bounds.iter().enumerate() {
+            let mut expanded = vec![Rational::zero(); width];
+            let (v, slack, value) = match *bound {
+                Bound::AtMost(v, u) => (v, Rational::one(), u),
+                Bound::AtLeast(v, l) => (v, -Rational::one(), l),
+            };
+            expanded[v] = Rational::one();
+            expanded[n + i] = slack;
+            a.push(expanded);
+            b.push(Rational::from(value));
+        }
+
+        let mut c: Vec<Rational> = self.objective.iter().map(|&v| Rational::from(v)).collect();
+        c.resize(width, Rational::zero());
+        (a, b, c)
+    }
+
+    fn relax(&self, bounds: &[Bound]) -> Relaxation {
+        let (a, b, c) = self.standard_form(bounds);
+        match simplex(&a, &b, &c) {
+            Relaxation::Solved { value, mut x } => {
+                x.truncate(self.variables());
+                Relaxation::Solved { value, x }
+            }
+            other => other,
+        }
+    }
+
+    /// Check a proof against this program without trusting the solver.
+    pub fn verify(&self, proof: &Proof) -> bool {
+        let mut initial: Vec<Bound> = self
+            .upper_bounds
+            .iter()
+            .enumerate()
+            .filter_map(|(v, upper)| upper.map(|u| Bound::AtMost(v, u)))
+            .collect();
+        self.verify_at(proof, &mut initial)
+    }
+
+    fn verify_at(&self, proof: &Proof, bounds: &mut Vec<Bound>) -> bool {
+        match proof {
+            Proof::Farkas(multipliers) => {
+                let (a, b, _) = self.standard_form(bounds);
+                if multipliers.len() != a.len() {
+                    return false;
+                }
+                let combined_rhs = multipliers
+                    .iter()
+                    .zip(&b)
+                    .fold(Rational::zero(), |acc, (&y, &rhs)| acc + y * rhs);
+                let columns_ok = (0..a.first().map_or(0, |row| row.len())).all(|col| {
+                    let combined = multipliers
+                        .iter()
+                        .zip(&a)
+                        .fold(Rational::zero(), |acc, (&y, row)| acc + y * row[col]);
+                    combined <= Rational::zero()
+                });
+                columns_ok && combined_rhs > Rational::zero()
+            }
+            Proof::Branch { variable, at, below, above } => {
+                bounds.push(Bound::AtMost(*variable, *at));
+                let below_ok = self.verify_at(below, bounds);
+                bounds.pop();
+                bounds.push(Bound::AtLeast(*variable, *at + 1));
+                let above_ok = self.verify_at(above, bounds);
+                bounds.pop();
+                below_ok && above_ok
+            }
+        }
+    }
+}
+
+struct Search<'a> {
+    program: &'a IntegerProgram,
+    best: Option<(i64, Vec<i64>)>,
+    unbounded: bool,
+    /// The lowest LP bound of any node that was too deep to branch on.
+    cut_off: Option<Rational>,
+}
+
+impl Search<'_> {
+    /* Depth first branch and bound. Returns a proof when this node provably has
+     * no integer solution at all, None when it found one or got cut off by the
+     * best answer so far (in which case there's an answer, no proof needed), or
+     * was too deep to go on with.
+     */
+    fn branch(&mut self, bounds: &mut Vec<Bound>, depth: usize) -> Option<Proof> {
+        let (value, x) = match self.program.relax(bounds) {
+            Relaxation::Infeasible(multipliers) => return Some(Proof::Farkas(multipliers)),
+            Relaxation::Unbounded => {
+                self.unbounded = true;
+                return None;
+            }
+            Relaxation::Solved { value, x } => (value, x),
+        };
+
+        // The objective has whole coefficients so any integer answer is at least ceil(value).
+        if let Some((best, _)) = &self.best
+            && value.ceil() >= *best as i128
+        {
+            return None;
+        }
+
+        let Some((variable, fractional)) = x.iter().enumerate().find(|(_, v)| !v.is_integer()) else {
+            let solution = x.iter().map(|v| v.numer() as i64).collect();
+            self.best = Some((value.numer() as i64, solution));
+            return None;
+        };
+
+        if depth == MOST_BRANCHES {
+            self.cut_off = Some(self.cut_off.map_or(value, |lowest| lowest.min(value)));
+            return None;
+        }
+
+        let at = fractional.floor() as i64;
+        bounds.push(Bound::AtMost(variable, at));
+        let below = self.branch(bounds, depth + 1);
+        bounds.pop();
+
+        bounds.push(Bound::AtLeast(variable, at + 1));
+        let above = self.branch(bounds, depth + 1);
+        bounds.pop();
+
+        match (below, above) {
+            (Some(below), Some(above)) => Some(Proof::Branch {
+                variable,
+                at,
+                below: Box::new(below),
+                above: Box::new(above),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/* Dense tableau with Bland's rule so it can't cycle. Each row is the
+ * coefficients followed by the right hand side, `cost` holds the reduced
+ * costs with the negated objective value in its last slot.
+ */
+struct Tableau {
+    rows: Vec<Vec<Rational>>,
+    cost: Vec<Rational>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn rhs(&self, r: usize) -> Rational {
+        self.rows[r][self.rows[r].len() - 1]
+    }
+
+    fn pivot(&mut self, r: usize, c: usize) {
+        let pivot_value = self.rows[r][c];
+        for val in self.rows[r].iter_mut() {
+            *val = *val / pivot_value;
+        }
+
+        let pivot_row = self.rows[r].clone();
+        for (i, row) in self.rows.iter_mut().enumerate() {
+            if i == r || row[c].is_zero() {
+                continue;
+            }
+            let factor = row[c];
+            for (val, &p) in row.iter_mut().zip(&pivot_row) {
+                *val = *val - factor * p;
+            }
+        }
+
+        let factor = self.cost[c];
+        if !factor.is_zero() {
+            for (val, &p) in self.cost.iter_mut().zip(&pivot_row) {
+                *val = *val - factor * p;
+            }
+        }
+
+        self.basis[r] = c;
+    }
+
+    /// Run the simplex using only the first `allowed` columns. Err means unbounded.
+    fn optimize(&mut self, allowed: usize) -> Result<(), ()> {
+        loop {
+            let Some(entering) = (0..allowed).find(|&c| self.cost[c] < Rational::zero()) else {
+                return Ok(());
+            };
+
+            let mut leaving: Option<(usize, Rational)> = None;
+            for r in 0..self.rows.len() {
+                let coefficient = self.rows[r][entering];
+                if coefficient <= Rational::zero() {
+                    continue;
+                }
+                let ratio = self.rhs(r) / coefficient;
+                leaving = match leaving {
+                    Some((best, best_ratio))
+                        if best_ratio < ratio
+                            || (best_ratio == ratio && self.basis[best] < self.basis[r]) =>
+                    {
+                        Some((best, best_ratio))
+                    }
+                    _ => Some((r, ratio)),
+                };
+            }
+
+            let Some((leaving, _)) = leaving else {
+                return Err(());
+            };
+            self.pivot(leaving, entering);
+        }
+    }
+}
+
+/* Two phase simplex for min c.x with A x = b and x >= 0. */
+fn simplex(a: &[Vec<Rational>], b: &[Rational], c: &[Rational]) -> Relaxation {
+    let m = a.len();
+    let n = c.len();
+
+    // Phase 1 wants b >= 0, so flip any row that isn't and remember we did.
+    let signs: Vec<Rational> = b
+        .iter()
+        .map(|&v| if v < Rational::zero() { -Rational::one() } else { Rational::one() })
+        .collect();
+
+    let rows: Vec<Vec<Rational>> = (0..m)
+        .map(|r| {
+            let mut row: Vec<Rational> = a[r].iter().map(|&v| v * signs[r]).collect();
+            row.extend((0..m).map(|i| if i == r { Rational::one() } else { Rational::zero() }));
+            row.push(b[r] * signs[r]);
+            row
+        })
+        .collect();
+
+    // Artificial variables all cost one and start in the basis, so the reduced
+    // cost of a real column is minus its column sum.
+    let mut cost = vec![Rational::zero(); n + m + 1];
+    for row in &rows {
+        for c in (0..n).chain([n + m]) {
+            cost[c] = cost[c] - row[c];
+        }
+    }
+
+    let mut tableau = Tableau { rows, cost, basis: (n..n + m).collect() };
+    tableau
+        .optimize(n + m)
+        .expect("phase 1 is bounded below by zero");
+
+    if -tableau.cost[n + m] > Rational::zero() {
+        // The phase 1 duals are one minus the reduced costs of the artificials.
+        let multipliers = (0..m)
+            .map(|i| (Rational::one() - tableau.cost[n + i]) * signs[i])
+            .collect();
+        return Relaxation::Infeasible(multipliers);
+    }
+
+    // Push any artificials still sitting at zero out of the basis. If a row has
+    // nothing real to pivot on it was redundant and can go.
+    let mut r = 0;
+    while r < tableau.rows.len() {
+        if tableau.basis[r] >= n {
+            if let Some(c) = (0..n).find(|&c| !tableau.rows[r][c].is_zero()) {
+                tableau.pivot(r, c);
+            } else {
+                tableau.rows.remove(r);
+                tableau.basis.remove(r);
+                continue;
+            }
+        }
+        r += 1;
+    }
+
+    // Phase 2 with the real costs.
+    let mut cost = vec![Rational::zero(); n + m + 1];
+    cost[..n].copy_from_slice(c);
+    for (row, &basic) in tableau.rows.iter().zip(&tableau.basis) {
+        let factor = c[basic];
+        if factor.is_zero() {
+            continue;
+        }
+        for (val, &entry) in cost.iter_mut().zip(row) {
+            *val = *val - factor * entry;
+        }
+    }
+    tableau.cost = cost;
+
+    if tableau.optimize(n).is_err() {
+        return Relaxation::Unbounded;
+    }
+
+    let mut x = vec![Rational::zero(); n];
+    for (r, &basic) in tableau.basis.iter().enumerate() {
+        x[basic] = tableau.rhs(r);
+    }
+    Relaxation::Solved { value: -tableau.cost[n + m], x }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lp_optimum_is_already_whole() {
+        // x + y = 4, x - y = 2, min x + y
+        let program = IntegerProgram::new(vec![vec![1, 1], vec![1, -1]], vec![4, 2], vec![1, 1]);
+        assert_eq!(program.solve(), Outcome::Optimal { value: 4, solution: vec![3, 1] });
+    }
+
+    #[test]
+    fn test_branching_needed() {
+        // 2x + 2y + z = 5, min x + y + z. LP would love z = 0 and x + y = 2.5.
+        let program = IntegerProgram::new(vec![vec![2, 2, 1]], vec![5], vec![1, 1, 1]);
+        match program.solve() {
+            Outcome::Optimal { value, solution } => {
+                assert_eq!(value, 3);
+                assert_eq!(2 * solution[0] + 2 * solution[1] + solution[2], 5);
+            }
+            other => panic!("expected an optimum, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lp_infeasible_has_farkas_proof() {
+        // x + y = -1 can't happen with x, y >= 0.
+        let program = IntegerProgram::new(vec![vec![1, 1]], vec![-1], vec![1, 1]);
+        let Outcome::Infeasible(proof) = program.solve() else {
+            panic!("should be infeasible");
+        };
+        assert!(matches!(proof, Proof::Farkas(_)));
+        assert!(program.verify(&proof));
+    }
+
+    #[test]
+    fn test_integer_infeasible_has_branch_proof() {
+        // 2x = 1 is fine for the LP but not for whole numbers.
+        let program = IntegerProgram::new(vec![vec![2, 0], vec![0, 1]], vec![1, 3], vec![1, 1]);
+        let Outcome::Infeasible(proof) = program.solve() else {
+            panic!("should be infeasible");
+        };
+        assert!(matches!(proof, Proof::Branch { variable: 0, at: 0, .. }));
+        assert!(program.verify(&proof));
+    }
+
+    #[test]
+    fn test_unbounded_variables_give_up_instead_of_branching_forever() {
+        // 2x - 2y = 1 is odd on the right and even on the left, but the LP is
+        // happy at every node since x and y can always both go up.
+        let program = IntegerProgram::new(vec![vec![2, -2]], vec![1], vec![1, 1]);
+        assert_eq!(program.solve(), Outcome::Undecided);
+        // Bounding them is enough to prove it.
+        let bounded = program.with_upper_bounds(vec![Some(3), Some(3)]);
+        let Outcome::Infeasible(proof) = bounded.solve() else {
+            panic!("should be infeasible");
+        };
+        assert!(bounded.verify(&proof));
+    }
+
+    #[test]
+    fn test_upper_bounds_respected() {
+        // x + y = 5 with x <= 1 and x costing less than y.
+        let program = IntegerProgram::new(vec![vec![1, 1]], vec![5], vec![1, 2])
+            .with_upper_bounds(vec![Some(1), None]);
+        assert_eq!(program.solve(), Outcome::Optimal { value: 9, solution: vec![1, 4] });
+    }
+
+    #[test]
+    fn test_unbounded() {
+        // x - y = 0, minimize -x
+        let program = IntegerProgram::new(vec![vec![1, -1]], vec![0], vec![-1, 0]);
+        assert_eq!(program.solve(), Outcome::Unbounded);
+    }
+}
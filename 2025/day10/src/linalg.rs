@@ -0,0 +1,479 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/* Exact linear algebra. Both of the eliminations in this day used f64 and an
+ * EPSILON and it bit me more than once (see the episilon_problem tests), so
+ * this does the same Gaussian elimination but over a field where equality
+ * actually means equality. Rationals for the joltage counters, GF(2) for the
+ * lights since toggling a light twice is the same as not touching it.
+ */
+pub trait Field:
+    Copy
+    + PartialEq
+    + fmt::Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    a = a.abs();
+    b = b.abs();
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A fraction kept in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "rational with a zero denominator");
+        let sign = if den < 0 { -1 } else { 1 };
+        let divisor = gcd(num, den).max(1);
+        Rational {
+            num: sign * num / divisor,
+            den: sign * den / divisor,
+        }
+    }
+
+    pub fn integer(value: i128) -> Self {
+        Rational { num: value, den: 1 }
+    }
+
+    pub fn numer(self) -> i128 {
+        self.num
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.den == 1
+    }
+
+    pub fn floor(self) -> i128 {
+        self.num.div_euclid(self.den)
+    }
+
+    pub fn ceil(self) -> i128 {
+        -(-self.num).div_euclid(self.den)
+    }
+}
+
+// Nothing in this day needs the parts of a fraction on their own.
+#[allow(dead_code)]
+impl Rational {
+    pub fn denom(self) -> i128 {
+        self.den
+    }
+
+    pub fn to_integer(self) -> Option<i128> {
+        if self.is_integer() { Some(self.num) } else { None }
+    }
+}
+
+impl From<i64> for Rational {
+    fn from(value: i64) -> Self {
+        Rational::integer(value as i128)
+    }
+}
+
+impl From<usize> for Rational {
+    fn from(value: usize) -> Self {
+        Rational::integer(value as i128)
+    }
+}
+
+impl Add for Rational {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Rational::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        self + -rhs
+    }
+}
+
+impl Mul for Rational {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Rational::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        Rational::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+impl Neg for Rational {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Rational { num: -self.num, den: self.den }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Denominators are always positive so cross multiplying keeps the order.
+        (self.num * other.den).cmp(&(other.num * self.den))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+impl Field for Rational {
+    fn zero() -> Self {
+        Rational::integer(0)
+    }
+    fn one() -> Self {
+        Rational::integer(1)
+    }
+}
+
+/// The two element field. Addition is XOR and multiplication is AND.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Gf2(pub bool);
+
+impl From<u8> for Gf2 {
+    fn from(value: u8) -> Self {
+        Gf2(value & 1 == 1)
+    }
+}
+
+impl Add for Gf2 {
+    type Output = Self;
+    fn add(self, rhs: Self) -> Self {
+        Gf2(self.0 != rhs.0)
+    }
+}
+
+impl Sub for Gf2 {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self {
+        Gf2(self.0 != rhs.0)
+    }
+}
+
+impl Mul for Gf2 {
+    type Output = Self;
+    fn mul(self, rhs: Self) -> Self {
+        Gf2(self.0 && rhs.0)
+    }
+}
+
+impl Div for Gf2 {
+    type Output = Self;
+    fn div(self, rhs: Self) -> Self {
+        assert!(rhs.0, "division by zero in GF(2)");
+        self
+    }
+}
+
+impl Neg for Gf2 {
+    type Output = Self;
+    fn neg(self) -> Self {
+        self
+    }
+}
+
+impl Field for Gf2 {
+    fn zero() -> Self {
+        Gf2(false)
+    }
+    fn one() -> Self {
+        Gf2(true)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<F> {
+    pub data: Vec<Vec<F>>,
+    pub rows: usize,
+    pub cols: usize,
+}
+
+impl<F: Field> Matrix<F> {
+    pub fn new(data: Vec<Vec<F>>) -> Self {
+        let rows = data.len();
+        let cols = data.first().map_or(0, |row| row.len());
+        assert!(data.iter().all(|row| row.len() == cols), "ragged matrix");
+        Matrix { data, rows, cols }
+    }
+
+    /* Plain Gauss-Jordan elimination, but only columns below
+     * `pivot_limit` may hold a pivot. That lets an augmented [A | b] matrix be
+     * reduced without ever picking the right hand side as a pivot.
+     */
+    fn rref_limited(&self, pivot_limit: usize) -> Echelon<F> {
+        let mut data = self.data.clone();
+        let mut pivots = Vec::new();
+        let mut free = Vec::new();
+
+        let mut pivot_row = 0;
+        for col in 0..pivot_limit {
+            if pivot_row == self.rows {
+                free.push(col);
+                continue;
+            }
+
+            // Exact arithmetic means any non zero entry is as good as another.
+            let Some(best_row) = (pivot_row..self.rows).find(|&r| !data[r][col].is_zero()) else {
+                free.push(col);
+                continue;
+            };
+            data.swap(pivot_row, best_row);
+
+            let pivot_value = data[pivot_row][col];
+            for val in &mut data[pivot_row][col..] {
+                *val = *val / pivot_value;
+            }
+
+            let pivot_entries = data[pivot_row].clone();
+            for (r, row) in data.iter_mut().enumerate() {
+                if r == pivot_row || row[col].is_zero() {
+                    continue;
+                }
+                let factor = row[col];
+                for (val, &p) in row[col..].iter_mut().zip(&pivot_entries[col..]) {
+                    *val = *val - factor * p;
+                }
+            }
+
+            pivots.push(col);
+            pivot_row += 1;
+        }
+
+        Echelon {
+            matrix: Matrix { data, rows: self.rows, cols: self.cols },
+            pivots,
+            free,
+        }
+    }
+
+    /// Solve `self * x = rhs`. `None` when the system has no solution at all.
+    pub fn solve(&self, rhs: &[F]) -> Option<AffineSolution<F>> {
+        assert_eq!(rhs.len(), self.rows, "right hand side does not match the row count");
+        let augmented = Matrix {
+            data: self
+                .data
+                .iter()
+                .zip(rhs)
+                .map(|(row, &b)| {
+                    let mut row = row.clone();
+                    row.push(b);
+                    row
+                })
+                .collect(),
+            rows: self.rows,
+            cols: self.cols + 1,
+        };
+
+        let echelon = augmented.rref_limited(self.cols);
+        // A row of all zero coefficients with a non zero result is 0 = b.
+        if echelon.matrix.data[echelon.rank()..].iter().any(|row| !row[self.cols].is_zero()) {
+            return None;
+        }
+
+        Some(AffineSolution { echelon, variables: self.cols })
+    }
+}
+
+// This day always has a right hand side to solve against, these are for
+// looking at a plain matrix.
+#[allow(dead_code)]
+impl<F: Field> Matrix<F> {
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Matrix {
+            data: vec![vec![F::zero(); cols]; rows],
+            rows,
+            cols,
+        }
+    }
+
+    /// Reduced row echelon form of the whole matrix.
+    pub fn rref(&self) -> Echelon<F> {
+        self.rref_limited(self.cols)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.rref().rank()
+    }
+}
+
+/// A matrix in reduced row echelon form along with which columns got a pivot.
+#[derive(Debug, Clone)]
+pub struct Echelon<F> {
+    pub matrix: Matrix<F>,
+    /// `pivots[r]` is the column holding the leading one of row `r`.
+    pub pivots: Vec<usize>,
+    pub free: Vec<usize>,
+}
+
+impl<F: Field> Echelon<F> {
+    pub fn rank(&self) -> usize {
+        self.pivots.len()
+    }
+
+    /// One basis vector per free column: set that free column to one, the
+    /// others to zero, and read the pivot columns back out of the reduced rows.
+    pub fn null_space(&self) -> Vec<Vec<F>> {
+        self.free
+            .iter()
+            .map(|&f| {
+                let mut v = vec![F::zero(); self.free.len() + self.rank()];
+                v[f] = F::one();
+                for (r, &p) in self.pivots.iter().enumerate() {
+                    v[p] = -self.matrix.data[r][f];
+                }
+                v
+            })
+            .collect()
+    }
+}
+
+/// Every solution of a consistent system: one particular solution plus any
+/// combination of the null space.
+#[derive(Debug, Clone)]
+pub struct AffineSolution<F> {
+    /// The reduced augmented matrix, the last column is the right hand side.
+    pub echelon: Echelon<F>,
+    pub variables: usize,
+}
+
+impl<F: Field> AffineSolution<F> {
+    pub fn pivots(&self) -> &[usize] {
+        &self.echelon.pivots
+    }
+
+    pub fn free(&self) -> &[usize] {
+        &self.echelon.free
+    }
+
+    pub fn null_space(&self) -> Vec<Vec<F>> {
+        self.echelon.null_space()
+    }
+
+    /// The solution you get with every free variable set to zero.
+    pub fn particular(&self) -> Vec<F> {
+        self.evaluate(&vec![F::zero(); self.free().len()])
+    }
+
+    /// Fill in the pivot variables given values for the free ones (in the
+    /// same order as `free()`).
+    pub fn evaluate(&self, free_values: &[F]) -> Vec<F> {
+        assert_eq!(free_values.len(), self.free().len());
+        let mut x = vec![F::zero(); self.variables];
+        for (&f, &value) in self.free().iter().zip(free_values) {
+            x[f] = value;
+        }
+        for (r, &p) in self.pivots().iter().enumerate() {
+            let row = &self.echelon.matrix.data[r];
+            let mut value = row[self.variables];
+            for (&f, &free_value) in self.free().iter().zip(free_values) {
+                value = value - row[f] * free_value;
+            }
+            x[p] = value;
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn q(n: i128) -> Rational {
+        Rational::integer(n)
+    }
+
+    #[test]
+    fn test_rational_normalizes() {
+        assert_eq!(Rational::new(2, -4), Rational::new(-1, 2));
+        assert_eq!(Rational::new(7, 2).floor(), 3);
+        assert_eq!(Rational::new(-7, 2).floor(), -4);
+        assert_eq!(Rational::new(-7, 2).ceil(), -3);
+        assert!(Rational::new(1, 3) < Rational::new(1, 2));
+        assert_eq!(Rational::new(6, 4).denom(), 2);
+        assert_eq!(Rational::new(6, 4).to_integer(), None);
+        assert_eq!(Rational::new(-8, 4).to_integer(), Some(-2));
+    }
+
+    #[test]
+    fn test_rational_rank_and_null_space() {
+        let m = Matrix::new(vec![
+            vec![q(1), q(2), q(3)],
+            vec![q(2), q(4), q(6)],
+            vec![q(1), q(0), q(1)],
+        ]);
+        assert_eq!(m.rank(), 2);
+        let echelon = m.rref();
+        assert_eq!(echelon.pivots, vec![0, 1]);
+        assert_eq!(echelon.free, vec![2]);
+        assert_eq!(Matrix::<Rational>::zeros(3, 2).rank(), 0);
+
+        for v in echelon.null_space() {
+            for row in &m.data {
+                let dot = row.iter().zip(&v).fold(q(0), |acc, (&a, &b)| acc + a * b);
+                assert!(dot.is_zero());
+            }
+        }
+    }
+
+    #[test]
+    fn test_rational_solve_and_inconsistent() {
+        let m = Matrix::new(vec![vec![q(1), q(1)], vec![q(1), q(-1)]]);
+        let solution = m.solve(&[q(3), q(0)]).unwrap();
+        assert_eq!(solution.particular(), vec![Rational::new(3, 2), Rational::new(3, 2)]);
+
+        let m = Matrix::new(vec![vec![q(1), q(1)], vec![q(2), q(2)]]);
+        assert!(m.solve(&[q(1), q(3)]).is_none());
+    }
+
+    #[test]
+    fn test_gf2_solve() {
+        // x0 ^ x1 = 1, x1 ^ x2 = 0
+        let m: Matrix<Gf2> = Matrix::new(vec![
+            vec![1u8, 1, 0].into_iter().map(Gf2::from).collect(),
+            vec![0u8, 1, 1].into_iter().map(Gf2::from).collect(),
+        ]);
+        let solution = m.solve(&[Gf2(true), Gf2(false)]).unwrap();
+        assert_eq!(solution.free(), &[2]);
+        assert_eq!(solution.particular(), vec![Gf2(true), Gf2(false), Gf2(false)]);
+        assert_eq!(solution.evaluate(&[Gf2(true)]), vec![Gf2(false), Gf2(true), Gf2(true)]);
+    }
+}
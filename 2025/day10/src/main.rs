@@ -1,9 +1,10 @@
 use std::fs;
 use std::time::Instant;
-use std::collections::{HashSet, VecDeque};
 
-mod icub3;
-use icub3::Matrix;
+mod ilp;
+use ilp::{IntegerProgram, Outcome, Proof};
+mod linalg;
+use linalg::Gf2;
 
 
 fn main() {
@@ -18,7 +19,10 @@ fn main() {
     let i = Instant::now();
     let result = p2(raw_data);
     let time = i.elapsed();
-    println!("{:?}", result);
+    match result {
+        Ok(presses) => println!("{:?}", presses),
+        Err(problem) => println!("{:?}", problem),
+    }
     println!("Took: {:?}", time);
 }
 
@@ -33,14 +37,24 @@ fn p1(raw_data: &str) -> ResultType {
     total_presses
 }
 
-fn p2(raw_data: &str) -> ResultType {
+fn p2(raw_data: &str) -> Result<ResultType, JoltageError> {
     let configurations: Vec<(Vec<u8>, Vec<Vec<u8>>, Vec<usize>)> = raw_data.lines().map(|line| parse(line)).collect();
     let mut total_presses = 0;
     for configuration in configurations {
         let (_, buttons, joltages) = configuration;
-        total_presses += fewest_presses_with_joltage(buttons, joltages);
+        total_presses += fewest_presses_with_joltage(buttons, joltages)?;
     }
-    total_presses
+    Ok(total_presses)
+}
+
+#[derive(Debug, PartialEq)]
+enum JoltageError {
+    /// No amount of pressing hits the targets, and the proof of it.
+    Unreachable(Proof),
+    /// The solver said it was unreachable but its proof doesn't check out.
+    BadProof(Proof),
+    /// The solver gave up before it could tell.
+    Undecided,
 }
 
 
@@ -83,56 +97,57 @@ fn parse(line: &str) -> (Vec<u8>, Vec<Vec<u8>>, Vec<usize>) {
 }
 
 fn fewest_presses(goal: Vec<u8>, buttons: Vec<Vec<u8>>) -> usize {
-    let n = goal.len();
-    let start = vec![0u8; n];
-
-    if start == goal {
-        return 0;
-    }
-
-    // We just BFS to explore all options brute force for now
-    let mut q = VecDeque::new();
-    q.push_back((start.clone(), 0usize));
-    let mut visited = HashSet::new();
-    visited.insert(start);
+    /* Pressing a button twice undoes it, so every button is pressed zero or one
+     * times and the lights are just a system of equations over GF(2):
+     *
+     *   one row per light, one column per button, goal on the right.
+     *
+     * Solve that exactly, then walk every combination of the null space to find
+     * the solution with the fewest buttons set. Way better than the BFS.
+     */
+    let lights = linalg::Matrix::new(
+        (0..goal.len())
+            .map(|light| buttons.iter().map(|button| Gf2::from(button[light])).collect())
+            .collect(),
+    );
+    let target: Vec<Gf2> = goal.iter().map(|&g| Gf2::from(g)).collect();
 
-    while let Some((state, dist)) = q.pop_front() {
-        for btn in &buttons {
-            let next = apply_button(state.clone(), btn);
+    let Some(solution) = lights.solve(&target) else {
+        // arbitrary nonsense.
+        println!("{:?} {:?}", goal, buttons);
+        return usize::MAX;
+    };
 
-            if !visited.contains(&next) {
-                if machine_done(&next, &goal) {
-                    return dist + 1;
+    let particular = solution.particular();
+    let null_space = solution.null_space();
+    let mut fewest = usize::MAX;
+    for combination in 0..(1u64 << null_space.len()) {
+        let mut presses = particular.clone();
+        for (i, direction) in null_space.iter().enumerate() {
+            if combination & (1 << i) != 0 {
+                for (pressed, &flip) in presses.iter_mut().zip(direction) {
+                    *pressed = *pressed + flip;
                 }
-
-                visited.insert(next.clone());
-                q.push_back((next, dist + 1));
             }
         }
+        fewest = fewest.min(presses.iter().filter(|pressed| pressed.0).count());
     }
-
-    // arbitrary nonsense.
-    println!("{:?} {:?}", goal, buttons);
-    usize::MAX
+    fewest
 }
 
-fn apply_button(mut machine: Vec<u8>, button_click: &[u8]) -> Vec<u8> {
-    for i in 0..machine.len() {
-        machine[i] = machine[i] ^ button_click[i];
+fn fewest_presses_with_joltage(buttons: Vec<Vec<u8>>, jolt_goal: Vec<usize>) -> Result<usize, JoltageError> {
+    let program = joltage_program(&buttons, &jolt_goal);
+    match program.solve() {
+        Outcome::Optimal { value, .. } => Ok(value as usize),
+        // Don't take the solver's word for it.
+        Outcome::Infeasible(proof) if program.verify(&proof) => Err(JoltageError::Unreachable(proof)),
+        Outcome::Infeasible(proof) => Err(JoltageError::BadProof(proof)),
+        Outcome::Undecided => Err(JoltageError::Undecided),
+        Outcome::Unbounded => unreachable!("presses cost 1 each so it can't go below 0"),
     }
-    machine
 }
 
-fn machine_done(machine: &Vec<u8>, goal: &Vec<u8>) -> bool {
-    for i in 0..machine.len() {
-        if machine[i] != goal[i] {
-            return false
-        }
-    }
-    true
-}
-
-fn fewest_presses_with_joltage(buttons: Vec<Vec<u8>>, jolt_goal: Vec<usize>) -> usize {
+fn joltage_program(buttons: &[Vec<u8>], jolt_goal: &[usize]) -> IntegerProgram {
     /* The input comes in like this:
      * [ [1, 0], [0,1] ]      [1, 1]
      * and then the goal is to know we should press the buttons once each to make 
@@ -167,336 +182,31 @@ fn fewest_presses_with_joltage(buttons: Vec<Vec<u8>>, jolt_goal: Vec<usize>) ->
      * But first... convert the button input into a matrix since matrices are a good way to deal with 
      * computing equations Linear algebra baby! one row per joltage output!
      */
-    let height = jolt_goal.len();
-    let width = 1 + buttons.len(); // 1 + because it's a + b = c and we need space for c in the matrix.
-    let mut matrix: Vec<Vec<f64>> = Vec::new();
-    for _ in 0..height {
-        let row = vec![0f64; width];
-        matrix.push(row);
-    }
-
-    for (r, coefficient) in jolt_goal.clone().into_iter().enumerate() {
-        matrix[r][width - 1] = coefficient as f64;
-    }
-
-    for b in 0..buttons.len() {
-        let button = &buttons[b];
+    let mut constraints = vec![vec![0i64; buttons.len()]; jolt_goal.len()];
+    for (b, button) in buttons.iter().enumerate() {
         for (c, affects) in button.iter().enumerate() {
             if *affects == 1  {
-                matrix[c][b] = 1.0;
-            }
-        }
-    }
-
-    /* For checking, this code will work. */
-    solit(buttons.clone(), jolt_goal.clone());
-
-    // we'll get back how many presses of each button needs to happen
-    // so the total presses is just heir sum
-    let presses = gauss_it_up(matrix, buttons.len());
-    presses.iter().sum()
-}
-
-fn solit(buttons: Vec<Vec<u8>>, jolt_goal: Vec<usize>) -> usize {
-    let height = jolt_goal.len();
-    let width = 1 + buttons.len(); // 1 + because it's a + b = c and we need space for c in the matrix.
-    let mut matrix: Vec<Vec<f64>> = Vec::new();
-    for _ in 0..height {
-        let row = vec![0f64; width];
-        matrix.push(row);
-    }
-
-    for (r, coefficient) in jolt_goal.clone().into_iter().enumerate() {
-        matrix[r][width - 1] = coefficient as f64;
-    }
-
-    for b in 0..buttons.len() {
-        let button = &buttons[b];
-        for (c, affects) in button.iter().enumerate() {
-            if *affects == 1  {
-                matrix[c][b] = 1.0;
-            }
-        }
-    }
-
-    let mut m = Matrix {
-        data: matrix.clone(),
-        rows: height,
-        cols: width - 1,
-        dependents: Vec::new(),
-        independents: Vec::new(),
-    };
-
-    m.gaussian_elimination();
-    
-    let max = jolt_goal.clone().iter().max().unwrap() + 1;
-    let mut min = usize::MAX;
-    let mut values = vec![0; m.independents.len()];
-
-
-    icub3::dfs(&m, 0, &mut values, &mut min, max);
-    println!("SOLUTION HERE {:?}", values);
-
-    min
-}
-fn gauss_it_up(matrix: Vec<Vec<f64>>, variable_count: usize) -> Vec<usize> {
-    /* Matrices are hard. I kind of feel like grabbing a library to do this sort
-     * of thing would PROBABLY be the better option here. But eh. Let's see if we
-     * can figure it out.
-     * 
-     * First off, our matrix isn't in the normalized form we need to do solving.
-     * So, first we need to put it into that!
-     */
-    let rows = matrix.len();
-    let columns = matrix[0].len();
-
-    let mut normalized_matrix: Vec<Vec<f64>> = Vec::new();
-    for r in 0..rows {
-        normalized_matrix.push(Vec::new());
-        for c in 0..columns {
-            normalized_matrix[r].push(matrix[r][c]);
-        }
-    }
-
-    /* We need to find the pivot of the matrix */
-    let mut pivot_row = 0;
-    let mut pivol_column = vec![usize::MAX; rows];
-    let mut is_pivot_column_tracker = vec![false; variable_count];
-
-    let mut col = 0;
-    loop {
-        /* I dont remember how to for (x=y;condition;++) in rust */
-        if col >= variable_count || pivot_row >= rows {
-            break;
-        }
-
-        let mut row = pivot_row;
-        let mut best_row = usize::MAX;
-
-        loop {
-            if row >= rows {
-                break;
-            }
-            if normalized_matrix[row][col].abs() > 1e-4 {
-                if best_row == usize::MAX || (normalized_matrix[row][col].abs() - 1.0).abs() < 1e-4 {
-                    best_row = row;
-
-                    if normalized_matrix[row][col].abs() == 1.0 {
-                        break;
-                    }
-                }
-            }
-
-            row += 1;
-        }
-
-        /* No best row found, so check the next one. */
-        if best_row == usize::MAX {
-            col += 1;
-            continue;
-        }
-
-        /* Rust has a swap! no clone() or anything :)  */
-        normalized_matrix.swap(best_row, pivot_row);
-
-        /* Track the pivot, and then... */
-        pivol_column[pivot_row] = col;
-        is_pivot_column_tracker[col] = true;
-
-        /* DONT FORGET TO NORMALIZE! */
-        let pivot = normalized_matrix[pivot_row][col];
-        if pivot.abs() > 1e-4 && (pivot - 1.0).abs() > 1e-4 {
-            for c in 0..columns {
-                normalized_matrix[pivot_row][c] /= pivot;
-            }
-        }
-
-
-        /* We can now elimate this column in other rows */
-        for r in 0..rows {
-            if r != pivot_row && normalized_matrix[r][col].abs() > 1e-4{
-                let factor = normalized_matrix[r][col];
-                for c in 0..columns {
-                    normalized_matrix[r][c] -= factor * normalized_matrix[pivot_row][c];
-                }
+                constraints[c][b] = 1;
             }
         }
-
-
-        /* Don't forget to bump the "for loop" variables up and also shift the pivot row to the next row */
-        pivot_row += 1;
-        col += 1;
-    }
-
-    /* Assume that all inputs have a solution, so don't bother checking consistency 
-     * if we DO need to that, then check that each number is greater than 0.
-     */
-    for r in pivot_row..rows {
-        if normalized_matrix[r][columns - 1].abs() > 1e-4 {
-            println!("no solution found something is probably wrong");
-            return Vec::new(); // no solution
-        }
-    }
-
-    /*
-     * So now, we need to know about the variables that are floating around that we'll
-     * need to try to determine.
-     */
-    let mut free_variables = vec![];
-    for c in 0..variable_count {
-        if !is_pivot_column_tracker[c] {
-            free_variables.push(c);
-        }
     }
+    let targets: Vec<i64> = jolt_goal.iter().map(|&j| j as i64).collect();
 
-    /* If every variable is already bound, then we can check to see if we can return early
-     * without having to do any extra work:
+    /* Solving the system alone isn't enough since the free variables can be anything,
+     * so hand it to the integer program solver: every button press costs 1 and we
+     * want the cheapest whole, non negative set of presses. No more guessing at a
+     * max value for the free variables or sorting the buttons and hoping.
      */
-    if free_variables.is_empty() {
-        let mut solution = vec![0usize; variable_count];
-        for r in 0..pivot_row {
-            let col = pivol_column[r];
-            let rounded = normalized_matrix[r][columns - 1].round();
-            if (rounded - normalized_matrix[r][columns - 1]).abs() > 1e-4 || rounded < 0.0 {
-                println!("no solution sadge {:?}", matrix);
-                return Vec::new();
-            }
-            solution[col] = rounded as usize;
-        }
-        return solution;
-    }
-
-    /* But if we DO have a system of equations that has free variables, then we need to 
-     * search in the space of the potential values for those for something that works
-     * but since numbers are infinity, we'll constrain it arbitrarily based on if the AoC
-     * puzzle tells us if the number is too low or high.
-     * We can smartly constrain the maximum number of presses down to the sum of all the
-     * potential joltage targets since if we go past that the solution is garbage:
-    */
-    let maybe_solution = solve_for_free_variables(free_variables, &normalized_matrix, &pivol_column, pivot_row, variable_count);
-
-    maybe_solution
-}
-
-fn solve_for_free_variables(
-    free_vars: Vec<usize>,
-    normalized: &Vec<Vec<f64>>,
-    pivol_column: &Vec<usize>,
-    pivot_row: usize,
-    variable_count: usize,
-) -> Vec<usize> {
-    let joltage_column = normalized[0].len() - 1;
-
-    // max free variable = max RHS, capped at something small-ish
-    let max_target: f64 = normalized.iter().fold(0.0, |acc, row| acc.max(row[joltage_column]));
-    let max_free_variable_value = 501.0; // 100 was too little, 500 seems ok.
-
-    let mut current_solution = vec![0usize; variable_count];
-    let mut min_sum_found = usize::MAX;
-    let mut min_solution = None;
-
-    enumerate_free_vars(
-        0,
-        &free_vars,
-        normalized,
-        pivol_column,
-        pivot_row,
-        variable_count,
-        joltage_column,
-        max_free_variable_value as usize,
-        &mut current_solution,
-        0,
-        &mut min_sum_found,
-        &mut min_solution,
-    );
-
-    min_solution.unwrap_or_else(|| vec![0; variable_count])
-}
-
-/* This is complicated... internet solution found in the wilds */
-fn enumerate_free_vars(
-    idx: usize,
-    free_vars: &Vec<usize>,
-    normalized: &Vec<Vec<f64>>,
-    pivot_col: &Vec<usize>,
-    pivot_row_count: usize,
-    variable_count: usize,
-    rhs_col: usize,
-    max_free_value: usize,
-    current_solution: &mut Vec<usize>,
-    current_sum: usize,
-    min_sum: &mut usize,
-    best_solution: &mut Option<Vec<usize>>,
-) {
-    // prune
-    if current_sum >= *min_sum {
-        return;
-    }
-
-    // if we've assigned all free variables → do back-substitution for pivot variables
-    if idx == free_vars.len() {
-        let mut sol = current_solution.clone();
-        let rows = pivot_row_count;
-
-        for r in (0..rows).rev() {
-            let col = pivot_col[r];
-            // subtract contributions from all other variables
-            let mut value = normalized[r][rhs_col];
-            for c in 0..variable_count {
-                if c != col {
-                    let coeff = normalized[r][c];
-                    if coeff.abs() > 1e-9 {
-                        value -= coeff * sol[c] as f64;
-                    }
-                }
-            }
-
-            if value < -(1e-9) {
-                return; // invalid
-            }
-            let rounded = value.round();
-            if (value - rounded).abs() > 1e-9 {
-                return; // invalid
-            }
-            sol[col] = rounded as usize;
-        }
-
-        // evaluate total presses
-        let sum: usize = sol.iter().sum();
-        if sum < *min_sum {
-            *min_sum = sum;
-            *best_solution = Some(sol);
-        }
-
-        return;
-    }
-
-    // otherwise enumerate values for the current free variable
-    let free_var = free_vars[idx];
-
-    for val in 0..=max_free_value {
-        current_solution[free_var] = val;
-
-        enumerate_free_vars(
-            idx + 1,
-            free_vars,
-            normalized,
-            pivot_col,
-            pivot_row_count,
-            variable_count,
-            rhs_col,
-            max_free_value,
-            current_solution,
-            current_sum + val,
-            min_sum,
-            best_solution,
-        );
-    }
-
-    current_solution[free_var] = 0; // reset for cleanliness
+    // A button can't be pressed more times than the smallest counter it bumps.
+    let most_presses: Vec<Option<i64>> = buttons
+        .iter()
+        .map(|button| {
+            button.iter().zip(&targets).filter(|&(&affects, _)| affects == 1).map(|(_, &target)| target).min()
+        })
+        .collect();
+    IntegerProgram::new(constraints, targets, vec![1; buttons.len()]).with_upper_bounds(most_presses)
 }
 
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -588,43 +298,53 @@ mod tests {
     #[test]
     fn test_first_machine_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}");
-        assert_eq!(10, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(10), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_second_machine_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}");
-        assert_eq!(12, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(12), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_third_machine_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}");
-        assert_eq!(11, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(11), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_episilon_problem_machine_1_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[####] (0,1) (0,2,3) (2,3) {27,15,16,16}");
-        assert_eq!(31, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(31), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_episilon_problem_machine_2_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[.###] (2,3) (1,3) (0) (3) (0,2) (0,3) {187,1,28,28}");
-        assert_eq!(188, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(188), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_episilon_problem_machine_3_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[#.###..#] (0,1,2,3,4,6) (0,1,2,3,6,7) (0,1,2,4,6,7) (0,1,3,4,5) (0,2,6) (0,2,7) {70,41,50,29,39,20,32,32}");
-        assert_eq!(70, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(70), fewest_presses_with_joltage(buttons, joltage_goal));
     }
 
     #[test]
     fn test_episilon_problem_machine_4_with_joltage() {
         let (_, buttons, joltage_goal) = parse("[##....#...] (2,3,4,5,6,7) (0,1,2,3,4,6,9) (0,1,3,4,6,8,9) (1,2,3,4,6) (1,4,5,6,8,9) (2,4,5,8,9) (0,1,3,4,5,8,9) (0,1,2,6) (2,7,8,9) (1,2,4,8) (3,4,6,7,8,9) (0,2,5,7,8) (0,1,2,4,5,6,8,9) {79,95,119,80,137,88,128,63,122,126}");
-        assert_eq!(164, fewest_presses_with_joltage(buttons, joltage_goal));
+        assert_eq!(Ok(164), fewest_presses_with_joltage(buttons, joltage_goal));
+    }
+
+    #[test]
+    fn test_unreachable_joltage_has_a_proof() {
+        // The only button bumps both counters, so they can't end up different.
+        let (_, buttons, joltage_goal) = parse("[..] (0,1) {1,2}");
+        let Err(JoltageError::Unreachable(proof)) = fewest_presses_with_joltage(buttons.clone(), joltage_goal.clone()) else {
+            panic!("should be unreachable");
+        };
+        assert!(joltage_program(&buttons, &joltage_goal).verify(&proof));
     }
 
     
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+/* The devices and which ones feed into which. Everything gets a dense index
+ * so the counting can use plain Vecs.
+ *
+ * Counting paths by walking every one of them is exponential, but if there are
+ * no loops the number of paths from a device is just the sum over whatever it
+ * feeds into, so one pass in reverse topological order does it. Waypoints that
+ * have to be visited go in a bitmask next to the device, same idea as the
+ * (node, dac_seen, fft_seen) memo part 2 had, just for any number of them.
+ *
+ * Only the devices that are both reachable from the start and can reach the
+ * end matter, so a loop off to the side somewhere doesn't stop anything. A
+ * loop that's actually in the way means infinitely many walks, so then it's an
+ * error, or count_simple_paths if you want to try your luck.
+ */
+
+#[derive(Debug, PartialEq)]
+pub enum GraphError {
+    MissingColon(String),
+    DuplicateDevice(String),
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    UnknownDevice(String),
+    // Bitmask of waypoints per device, more than this is a lot of memory.
+    TooManyWaypoints(usize),
+    // The devices going round the loop, in order.
+    Cycle(Vec<String>),
+    // count_simple_paths gave up after trying this many steps.
+    GaveUp(usize),
+    Overflow,
+}
+
+pub const MAX_WAYPOINTS: usize = 16;
+
+#[derive(Debug)]
+pub struct DeviceGraph<'a> {
+    names: Vec<&'a str>,
+    index: HashMap<&'a str, usize>,
+    outputs: Vec<Vec<usize>>,
+    inputs: Vec<Vec<usize>>,
+}
+
+impl<'a> TryFrom<&'a str> for DeviceGraph<'a> {
+    type Error = GraphError;
+
+    // aaa: you hhh
+    fn try_from(raw_data: &'a str) -> Result<DeviceGraph<'a>, GraphError> {
+        let mut graph = DeviceGraph {
+            names: Vec::new(),
+            index: HashMap::new(),
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+        };
+        let mut described = Vec::new();
+
+        for line in raw_data.lines().filter(|l| !l.is_empty()) {
+            let (left, rest) = line.split_once(':').ok_or_else(|| GraphError::MissingColon(line.to_string()))?;
+            let from = graph.id_or_insert(left.trim());
+            if described.contains(&from) {
+                return Err(GraphError::DuplicateDevice(left.trim().to_string()));
+            }
+            described.push(from);
+            for right in rest.split_whitespace() {
+                let to = graph.id_or_insert(right);
+                graph.outputs[from].push(to);
+                graph.inputs[to].push(from);
+            }
+        }
+        Ok(graph)
+    }
+}
+
+impl<'a> DeviceGraph<'a> {
+    fn id_or_insert(&mut self, name: &'a str) -> usize {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name);
+        self.index.insert(name, id);
+        self.outputs.push(Vec::new());
+        self.inputs.push(Vec::new());
+        id
+    }
+
+    fn id(&self, name: &str) -> Result<usize, PathError> {
+        self.index.get(name).copied().ok_or_else(|| PathError::UnknownDevice(name.to_string()))
+    }
+
+    /// No loops anywhere, not just between two particular devices.
+    pub fn is_dag(&self) -> bool {
+        self.topological_order(&vec![true; self.names.len()]).is_ok()
+    }
+
+    // Everything reachable from start following `edges`.
+    fn reachable(&self, start: usize, edges: &[Vec<usize>]) -> Vec<bool> {
+        let mut seen = vec![false; self.names.len()];
+        let mut stack = vec![start];
+        seen[start] = true;
+        while let Some(node) = stack.pop() {
+            for &next in &edges[node] {
+                if !seen[next] {
+                    seen[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    // Devices on some walk from `from` to `to`.
+    fn between(&self, from: usize, to: usize) -> Vec<bool> {
+        let forward = self.reachable(from, &self.outputs);
+        let backward = self.reachable(to, &self.inputs);
+        forward.iter().zip(&backward).map(|(&f, &b)| f && b).collect()
+    }
+
+    /// Kahn's algorithm over just the devices in `keep`. If it gets stuck
+    /// everything left over is on or behind a loop, so walk backwards through
+    /// what's left until a device repeats to find one.
+    fn topological_order(&self, keep: &[bool]) -> Result<Vec<usize>, PathError> {
+        let n = self.names.len();
+        let mut waiting_on: Vec<usize> = (0..n)
+            .map(|node| self.inputs[node].iter().filter(|&&i| keep[i]).count())
+            .collect();
+        let mut ready: Vec<usize> = (0..n).filter(|&node| keep[node] && waiting_on[node] == 0).collect();
+        let mut order = Vec::new();
+
+        while let Some(node) = ready.pop() {
+            order.push(node);
+            for &next in &self.outputs[node] {
+                if keep[next] {
+                    waiting_on[next] -= 1;
+                    if waiting_on[next] == 0 {
+                        ready.push(next);
+                    }
+                }
+            }
+        }
+
+        if order.len() == keep.iter().filter(|&&k| k).count() {
+            return Ok(order);
+        }
+
+        let stuck = |node: usize| keep[node] && waiting_on[node] > 0;
+        let mut node = (0..n).find(|&node| stuck(node)).unwrap();
+        let mut walked = vec![node];
+        loop {
+            // Something stuck is always waiting on something else that's stuck.
+            node = *self.inputs[node].iter().find(|&&i| stuck(i)).unwrap();
+            if let Some(at) = walked.iter().position(|&w| w == node) {
+                let mut cycle: Vec<String> = walked[at..].iter().map(|&w| self.names[w].to_string()).collect();
+                cycle.reverse();
+                return Err(PathError::Cycle(cycle));
+            }
+            walked.push(node);
+        }
+    }
+
+    fn waypoint_bits(&self, waypoints: &[&str]) -> Result<Vec<u32>, PathError> {
+        if waypoints.len() > MAX_WAYPOINTS {
+            return Err(PathError::TooManyWaypoints(waypoints.len()));
+        }
+        let mut bits = vec![0u32; self.names.len()];
+        for (i, name) in waypoints.iter().enumerate() {
+            bits[self.id(name)?] |= 1 << i;
+        }
+        Ok(bits)
+    }
+
+    /// How many paths go from `from` to `to` through every one of `waypoints`,
+    /// in any order. Errors out if there's a loop on the way.
+    pub fn count_paths(&self, from: &str, to: &str, waypoints: &[&str]) -> Result<u128, PathError> {
+        let (from, to) = (self.id(from)?, self.id(to)?);
+        let bits = self.waypoint_bits(waypoints)?;
+        let all = (1usize << waypoints.len()) - 1;
+        let keep = self.between(from, to);
+        if !keep[from] {
+            return Ok(0);
+        }
+        let order = self.topological_order(&keep)?;
+
+        // paths[node][seen] is how many ways there are to finish from node when
+        // `seen` waypoints are done already, node's own included.
+        let mut paths = vec![Vec::new(); self.names.len()];
+        for &node in order.iter().rev() {
+            let mut here = vec![0u128; all + 1];
+            if node == to {
+                here[all] = 1;
+            } else {
+                for (seen, count) in here.iter_mut().enumerate() {
+                    for &next in self.outputs[node].iter().filter(|&&next| keep[next]) {
+                        let ways: &Vec<u128> = &paths[next];
+                        *count = count.checked_add(ways[seen | bits[next] as usize]).ok_or(PathError::Overflow)?;
+                    }
+                }
+            }
+            paths[node] = here;
+        }
+        Ok(paths[from][bits[from] as usize])
+    }
+
+    /// Paths that never visit a device twice, found by walking every one of
+    /// them, so this works with loops but is only for small graphs. Gives up
+    /// after `max_steps` devices have been stepped onto.
+    pub fn count_simple_paths(&self, from: &str, to: &str, waypoints: &[&str], max_steps: usize) -> Result<u128, PathError> {
+        let (from, to) = (self.id(from)?, self.id(to)?);
+        let bits = self.waypoint_bits(waypoints)?;
+        let mut walk = SimpleWalk {
+            graph: self,
+            keep: self.between(from, to),
+            bits,
+            all: (1u32 << waypoints.len()) - 1,
+            to,
+            on_path: vec![false; self.names.len()],
+            steps: 0,
+            max_steps,
+        };
+        if !walk.keep[from] {
+            return Ok(0);
+        }
+        let seen = walk.bits[from];
+        walk.walk(from, seen)
+    }
+}
+
+struct SimpleWalk<'g, 'a> {
+    graph: &'g DeviceGraph<'a>,
+    keep: Vec<bool>,
+    bits: Vec<u32>,
+    all: u32,
+    to: usize,
+    on_path: Vec<bool>,
+    steps: usize,
+    max_steps: usize,
+}
+
+impl SimpleWalk<'_, '_> {
+    fn walk(&mut self, node: usize, seen: u32) -> Result<u128, PathError> {
+        self.steps += 1;
+        if self.steps > self.max_steps {
+            return Err(PathError::GaveUp(self.max_steps));
+        }
+        if node == self.to {
+            return Ok(if seen == self.all { 1 } else { 0 });
+        }
+
+        self.on_path[node] = true;
+        let mut total = 0u128;
+        for &next in &self.graph.outputs[node] {
+            if self.keep[next] && !self.on_path[next] {
+                let ways = self.walk(next, seen | self.bits[next]);
+                match ways {
+                    Ok(ways) => total = total.checked_add(ways).ok_or(PathError::Overflow)?,
+                    Err(problem) => {
+                        self.on_path[node] = false;
+                        return Err(problem);
+                    }
+                }
+            }
+        }
+        self.on_path[node] = false;
+        Ok(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PART_1: &str = "aaa: you hhh\nyou: bbb ccc\nbbb: ddd eee\nccc: ddd eee fff\nddd: ggg\neee: out\nfff: out\nggg: out\nhhh: ccc fff iii\niii: out\n";
+    const PART_2: &str = "svr: aaa bbb\naaa: fft\nfft: ccc\nbbb: tty\ntty: ccc\nccc: ddd eee\nddd: hub\nhub: fff\neee: dac\ndac: fff\nfff: ggg hhh\nggg: out\nhhh: out\n";
+
+    #[test]
+    fn test_examples() {
+        let graph = DeviceGraph::try_from(PART_1).unwrap();
+        assert!(graph.is_dag());
+        assert_eq!(graph.count_paths("you", "out", &[]), Ok(5));
+        assert_eq!(graph.count_simple_paths("you", "out", &[], 1000), Ok(5));
+
+        let graph = DeviceGraph::try_from(PART_2).unwrap();
+        assert_eq!(graph.count_paths("svr", "out", &[]), Ok(8));
+        assert_eq!(graph.count_paths("svr", "out", &["dac", "fft"]), Ok(2));
+        assert_eq!(graph.count_paths("svr", "out", &["fft", "dac"]), Ok(2));
+        assert_eq!(graph.count_paths("svr", "out", &["fft", "dac", "ggg"]), Ok(1));
+        assert_eq!(graph.count_paths("svr", "out", &["dac", "hub"]), Ok(0));
+        assert_eq!(graph.count_simple_paths("svr", "out", &["dac", "fft"], 1000), Ok(2));
+    }
+
+    #[test]
+    fn test_lots_of_paths() {
+        // 100 diamonds in a row is 2^100 paths, no chance of walking them.
+        let mut raw = String::new();
+        for i in 0..100 {
+            raw.push_str(&format!("n{i}: a{i} b{i}\na{i}: n{}\nb{i}: n{}\n", i + 1, i + 1));
+        }
+        let graph = DeviceGraph::try_from(raw.as_str()).unwrap();
+        assert_eq!(graph.count_paths("n0", "n100", &[]), Ok(1 << 100));
+        assert_eq!(graph.count_paths("n0", "n100", &["a7", "b50"]), Ok(1 << 98));
+        assert_eq!(graph.count_simple_paths("n0", "n100", &[], 10_000), Err(PathError::GaveUp(10_000)));
+    }
+
+    #[test]
+    fn test_loops() {
+        // The loop between ccc and ddd is in the way, the one at zzz isn't.
+        let graph = DeviceGraph::try_from("you: ccc zzz\nccc: ddd out\nddd: ccc out\nzzz: yyy\nyyy: zzz\n").unwrap();
+        assert!(!graph.is_dag());
+        assert_eq!(
+            graph.count_paths("you", "out", &[]),
+            Err(PathError::Cycle(vec!["ddd".to_string(), "ccc".to_string()]))
+        );
+        assert_eq!(graph.count_simple_paths("you", "out", &[], 1000), Ok(2));
+        assert_eq!(graph.count_simple_paths("you", "out", &["ddd"], 1000), Ok(1));
+
+        let graph = DeviceGraph::try_from("you: out zzz\nzzz: yyy\nyyy: zzz\n").unwrap();
+        assert_eq!(graph.count_paths("you", "out", &[]), Ok(1));
+    }
+
+    #[test]
+    fn test_bad_input() {
+        assert_eq!(DeviceGraph::try_from("aaa bbb\n").unwrap_err(), GraphError::MissingColon("aaa bbb".to_string()));
+        assert_eq!(DeviceGraph::try_from("a: b\na: c\n").unwrap_err(), GraphError::DuplicateDevice("a".to_string()));
+        let graph = DeviceGraph::try_from(PART_1).unwrap();
+        assert_eq!(graph.count_paths("you", "nope", &[]), Err(PathError::UnknownDevice("nope".to_string())));
+        assert_eq!(graph.count_paths("you", "out", &["aaa"; 17]), Err(PathError::TooManyWaypoints(17)));
+    }
+}
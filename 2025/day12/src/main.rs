@@ -1,120 +1,244 @@
 use std::fs;
 use std::time::Instant;
 
+mod packing;
+use packing::{Orientation, Packing};
+
 fn main() {
     let raw_data = fs::read_to_string("./input").expect("bad input data");
     let raw_data = raw_data.as_str();
     let i = Instant::now();
     let result = p1(raw_data);
     let time = i.elapsed();
-    println!("{:?}", result);
+    match result {
+        Ok(regions) => println!("{:?}", regions),
+        Err(problem) => println!("{:?}", problem),
+    }
     println!("Took: {:?}", time);
 }
 
-const NUM_MACHINES: usize = 6;
+#[derive(Debug, PartialEq)]
+enum ParseError {
+    BadShape(String),
+    BadRegion(String),
+    DimensionTooLarge(String),
+    WrongShapeCount(String),
+}
 
 type ResultType = i64;
-fn p1(raw_data: &str) -> ResultType {
+fn p1(raw_data: &str) -> Result<ResultType, ParseError> {
+    // Shapes come first, one block each, until the first line that looks like AxB: ...
     let shapes: Vec<Shape> = raw_data
         .split("\n\n")
-        .take(NUM_MACHINES)
-        .map(Shape::from)
-        .collect();
+        .take_while(|block| !block.lines().next().unwrap_or("").contains("x"))
+        .map(Shape::try_from)
+        .collect::<Result<_, _>>()?;
     let regions: Vec<Region> = raw_data
         .lines()
         .skip_while(|line| !line.contains("x"))
-        .map(Region::from)
-        .collect();
+        .filter(|line| !line.is_empty())
+        .map(Region::try_from)
+        .collect::<Result<_, _>>()?;
 
     let mut regions_able_to_fit_all_presents = 0;
     for region in regions {
+        if region.quantity_to_fit_per_shape.len() != shapes.len() {
+            return Err(ParseError::WrongShapeCount(format!(
+                "{}x{} wants {} shapes but there are {}",
+                region.width,
+                region.height,
+                region.quantity_to_fit_per_shape.len(),
+                shapes.len()
+            )));
+        }
         if region.can_fit(&shapes[..]) {
             regions_able_to_fit_all_presents += 1;
         }
     }
 
-    regions_able_to_fit_all_presents
+    Ok(regions_able_to_fit_all_presents)
 }
 
-#[derive(Debug, Clone, Copy)]
+/* Shapes used to be a fixed [[usize; 3]; 3] which was fine right up until
+ * someone hands us a 4x4 piece. Now it's a bitset of rows * cols bits in
+ * reading order, so any size polyomino works.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct Shape {
-    index: u8,
-    shape: [[usize; 3]; 3],
+    index: usize,
+    rows: usize,
+    cols: usize,
+    bits: Vec<u64>,
 }
 
-impl From<&str> for Shape {
+impl TryFrom<&str> for Shape {
+    type Error = ParseError;
+
     // Input is of the form, index:\n...\n###\n.... where # and . indicate shape or not
-    fn from(string: &str) -> Shape {
+    fn try_from(string: &str) -> Result<Shape, ParseError> {
         // Blog note: don't forget to use ! in front of line.is_empty
-        let mut lines = string
-            .lines()
-            .take_while(|line| !line.is_empty())
-            .enumerate();
+        let mut lines = string.lines().take_while(|line| !line.is_empty());
+
+        let bad = || ParseError::BadShape(string.to_string());
+        let idx = lines.next().and_then(|l| l.split_once(":")).ok_or_else(bad)?.0;
+        let index = idx.trim().parse().map_err(|_| bad())?;
+
+        let lines: Vec<&str> = lines.collect();
+        let rows = lines.len();
+        let cols = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut s = Shape::empty(index, rows, cols);
+
+        for (row, line) in lines.iter().enumerate() {
+            for (c, ch) in line.chars().enumerate() {
+                match ch {
+                    '#' => s.set(row, c),
+                    '.' => {}
+                    _ => return Err(bad()),
+                }
+            }
+        }
 
-        let idx = lines.next().unwrap().1.split_once(":").unwrap().0;
+        Ok(s)
+    }
+}
 
-        let mut s = Shape {
-            index: idx.parse().unwrap(),
-            shape: [[0, 0, 0], [0, 0, 0], [0, 0, 0]],
-        };
+impl Shape {
+    fn empty(index: usize, rows: usize, cols: usize) -> Shape {
+        Shape {
+            index,
+            rows,
+            cols,
+            bits: vec![0; (rows * cols).div_ceil(64)],
+        }
+    }
 
-        for (row, line) in lines {
-            for c in 0..3 {
-                s.shape[row - 1][c] = if let Some(ch) = line.chars().nth(c) {
-                    if ch == '#' { 1 } else { 0 }
-                } else {
-                    0
+    #[cfg(test)]
+    fn from_grid<const W: usize>(index: usize, grid: &[[usize; W]]) -> Shape {
+        let mut s = Shape::empty(index, grid.len(), W);
+        for (r, row) in grid.iter().enumerate() {
+            for (c, &cell) in row.iter().enumerate() {
+                if cell == 1 {
+                    s.set(r, c);
                 }
             }
         }
-
         s
     }
-}
 
-impl Shape {
-    fn rotated(&self) -> Shape {
-        let mut rotated_shape = [[0usize; 3]; 3];
+    fn get(&self, r: usize, c: usize) -> bool {
+        let bit = r * self.cols + c;
+        self.bits[bit / 64] & (1 << (bit % 64)) != 0
+    }
+
+    fn set(&mut self, r: usize, c: usize) {
+        let bit = r * self.cols + c;
+        self.bits[bit / 64] |= 1 << (bit % 64);
+    }
 
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.rows)
+            .flat_map(move |r| (0..self.cols).map(move |c| (r, c)))
+            .filter(|&(r, c)| self.get(r, c))
+    }
+
+    #[cfg(test)]
+    fn grid(&self) -> Vec<Vec<usize>> {
+        (0..self.rows)
+            .map(|r| (0..self.cols).map(|c| self.get(r, c) as usize).collect())
+            .collect()
+    }
+
+    fn rotated(&self) -> Shape {
         // rotate 90 degree clockwise
         // [1,2,3]
         // [4,5,6]
-        // [7,8,9]
         //   ------>|
         //          v
-        // [7,4,1]
-        // [8,5,2]
-        // [9,6,3]
-        // row 0 becomes col 2
-        // row 1 becomes col 1
-        // row 2 becomes col 0
+        // [4,1]
+        // [5,2]
+        // [6,3]
+        // row 0 becomes the last col, row 1 the one before it, and so on
+        let mut rotated_shape = Shape::empty(self.index, self.cols, self.rows);
+        for (r, c) in self.cells() {
+            rotated_shape.set(c, self.rows - 1 - r);
+        }
+        rotated_shape
+    }
 
-        rotated_shape[0][2] = self.shape[0][0];
-        rotated_shape[1][2] = self.shape[0][1];
-        rotated_shape[2][2] = self.shape[0][2];
+    fn flipped(&self) -> Shape {
+        // mirror left to right, so the first and last cols swap
+        let mut flipped_shape = Shape::empty(self.index, self.rows, self.cols);
+        for (r, c) in self.cells() {
+            flipped_shape.set(r, self.cols - 1 - c);
+        }
+        flipped_shape
+    }
 
-        rotated_shape[0][1] = self.shape[1][0];
-        rotated_shape[1][1] = self.shape[1][1];
-        rotated_shape[2][1] = self.shape[1][2];
+    // Trim off any empty rows and cols around the edges.
+    fn normalized(&self) -> Shape {
+        let cells: Vec<(usize, usize)> = self.cells().collect();
+        let (Some(top), Some(left)) = (
+            cells.iter().map(|&(r, _)| r).min(),
+            cells.iter().map(|&(_, c)| c).min(),
+        ) else {
+            return Shape::empty(self.index, 0, 0);
+        };
+        let bottom = cells.iter().map(|&(r, _)| r).max().unwrap();
+        let right = cells.iter().map(|&(_, c)| c).max().unwrap();
 
-        rotated_shape[0][0] = self.shape[2][0];
-        rotated_shape[1][0] = self.shape[2][1];
-        rotated_shape[2][0] = self.shape[2][2];
+        let mut trimmed = Shape::empty(self.index, bottom - top + 1, right - left + 1);
+        for (r, c) in cells {
+            trimmed.set(r - top, c - left);
+        }
+        trimmed
+    }
 
-        Shape {
-            index: self.index,
-            shape: rotated_shape,
+    // The same answer for a shape no matter how it was rotated, flipped, or
+    // padded, so two inputs that are really the same piece compare equal.
+    fn canonical(&self) -> Shape {
+        let mut all = Vec::new();
+        let mut shape = self.normalized();
+        for _ in 0..2 {
+            for _ in 0..4 {
+                all.push(shape.clone());
+                shape = shape.rotated();
+            }
+            shape = shape.flipped();
         }
+        all.into_iter()
+            .min_by(|a, b| (a.rows, a.cols, &a.bits).cmp(&(b.rows, b.cols, &b.bits)))
+            .unwrap()
     }
 
-    fn area(&self) -> usize {
-        let mut a = 0;
-        for r in 0..3 {
-            for c in 0..3 {
-                a += self.shape[r][c];
+    // Every distinct way the present can be put down, rotated and/or flipped.
+    // Symmetric shapes (like a plus) come out with fewer than 8.
+    fn orientations(&self) -> Vec<Orientation> {
+        let mut orientations: Vec<Orientation> = Vec::new();
+        let mut shape = self.clone();
+        for _ in 0..2 {
+            for _ in 0..4 {
+                let cells = shape.anchored_cells();
+                if !orientations.contains(&cells) {
+                    orientations.push(cells);
+                }
+                shape = shape.rotated();
             }
+            shape = shape.flipped();
         }
-        a
+        orientations
+    }
+
+    // Filled cells as offsets from the first filled cell in reading order.
+    fn anchored_cells(&self) -> Orientation {
+        let cells: Vec<(isize, isize)> = self.cells().map(|(r, c)| (r as isize, c as isize)).collect();
+        let Some(&(anchor_r, anchor_c)) = cells.first() else {
+            return cells;
+        };
+        cells.iter().map(|&(r, c)| (r - anchor_r, c - anchor_c)).collect()
+    }
+
+    fn area(&self) -> usize {
+        self.bits.iter().map(|word| word.count_ones() as usize).sum()
     }
 }
 
@@ -124,129 +248,230 @@ mod test_shapes {
 
     #[test]
     fn test_parse() {
-        let full: Shape = "0:\n###\n###\n###".into();
-        assert_eq!(full.shape, [[1, 1, 1], [1, 1, 1], [1, 1, 1]]);
+        let full = Shape::try_from("0:\n###\n###\n###").unwrap();
+        assert_eq!(full.grid(), [[1, 1, 1], [1, 1, 1], [1, 1, 1]]);
         assert_eq!(full.index, 0);
 
-        let partial: Shape = "1:\n.##\n#.#\n##.".into();
-        assert_eq!(partial.shape, [[0, 1, 1], [1, 0, 1], [1, 1, 0]]);
+        let partial = Shape::try_from("1:\n.##\n#.#\n##.").unwrap();
+        assert_eq!(partial.grid(), [[0, 1, 1], [1, 0, 1], [1, 1, 0]]);
         assert_eq!(partial.index, 1);
 
-        let none: Shape = "2:\n...\n...\n...".into();
-        assert_eq!(none.shape, [[0, 0, 0], [0, 0, 0], [0, 0, 0]]);
+        let none = Shape::try_from("2:\n...\n...\n...").unwrap();
+        assert_eq!(none.grid(), [[0, 0, 0], [0, 0, 0], [0, 0, 0]]);
         assert_eq!(none.index, 2);
     }
 
     #[test]
     fn test_rotation() {
         #[rustfmt::skip]
-        let shape = Shape {
-            index: 0,
-            shape: [
+        let shape = Shape::from_grid(
+            0,
+            &[
                 [1, 1, 0], 
                 [1, 0, 1], 
                 [0, 1, 0],
             ],
-        };
+        );
 
         #[rustfmt::skip]
-        let rotated = Shape {
-            index: 0,
-            shape: [
+        let rotated = Shape::from_grid(
+            0,
+            &[
                 [0, 1, 1], 
                 [1, 0, 1], 
                 [0, 1, 0],
             ],
-        };
-        assert_eq!(rotated.shape, shape.rotated().shape);
+        );
+        assert_eq!(rotated.grid(), shape.rotated().grid());
 
         #[rustfmt::skip]
-        let rotated = Shape {
-            index: 0,
-            shape: [
+        let rotated = Shape::from_grid(
+            0,
+            &[
                 [0, 1, 0], 
                 [1, 0, 1], 
                 [0, 1, 1],
             ],
-        };
-        assert_eq!(rotated.shape, shape.rotated().rotated().shape);
+        );
+        assert_eq!(rotated.grid(), shape.rotated().rotated().grid());
 
         #[rustfmt::skip]
-        let rotated = Shape {
-            index: 0,
-            shape: [
+        let rotated = Shape::from_grid(
+            0,
+            &[
                 [0, 1, 0], 
                 [1, 0, 1], 
                 [1, 1, 0],
             ],
-        };
-        assert_eq!(rotated.shape, shape.rotated().rotated().rotated().shape);
+        );
+        assert_eq!(rotated.grid(), shape.rotated().rotated().rotated().grid());
 
         // 4 90 degree turns is an identity function
         assert_eq!(
-            shape.shape,
-            shape.rotated().rotated().rotated().rotated().shape
+            shape.grid(),
+            shape.rotated().rotated().rotated().rotated().grid()
+        );
+    }
+
+    #[test]
+    fn test_orientations() {
+        // An L can be put down 8 different ways, a plus-ish H only 2.
+        #[rustfmt::skip]
+        let l = Shape::from_grid(
+            0,
+            &[
+                [1, 0, 0],
+                [1, 0, 0],
+                [1, 1, 0],
+            ],
         );
+        assert_eq!(l.orientations().len(), 8);
+
+        #[rustfmt::skip]
+        let h = Shape::from_grid(
+            1,
+            &[
+                [1, 1, 1],
+                [0, 1, 0],
+                [1, 1, 1],
+            ],
+        );
+        assert_eq!(h.orientations().len(), 2);
+        assert!(h.orientations().contains(&vec![(0, 0), (0, 2), (1, 0), (1, 1), (1, 2), (2, 0), (2, 2)]));
+    }
+
+    #[test]
+    fn test_parse_bigger_than_3x3() {
+        let big = Shape::try_from("7:\n#..#\n####\n.#").unwrap();
+        assert_eq!(big.index, 7);
+        assert_eq!(big.grid(), [[1, 0, 0, 1], [1, 1, 1, 1], [0, 1, 0, 0]]);
+        assert_eq!(big.area(), 7);
+        assert_eq!(big.rotated().grid(), [[0, 1, 1], [1, 1, 0], [0, 1, 0], [0, 1, 1]]);
+
+        assert!(matches!(Shape::try_from("0:\n#?#"), Err(ParseError::BadShape(_))));
+    }
+
+    #[test]
+    fn test_canonical() {
+        // Same S piece, once padded out and once flipped and turned on its side.
+        let padded = Shape::from_grid(0, &[[0, 0, 0, 0], [0, 1, 1, 0], [1, 1, 0, 0]]);
+        let turned = Shape::from_grid(1, &[[1, 0], [1, 1], [0, 1]]);
+        assert_eq!(padded.canonical().grid(), turned.canonical().grid());
+        assert_eq!(padded.normalized().grid(), [[0, 1, 1], [1, 1, 0]]);
     }
 
     #[test]
     fn test_area() {
         #[rustfmt::skip]
-        let shape = Shape {
-            index: 0,
-            shape: [
+        let shape = Shape::from_grid(
+            0,
+            &[
                 [1, 1, 0],
                 [1, 1, 1],
                 [0, 1, 0],
             ],
-        };
+        );
         assert_eq!(shape.area(), 6);
     }
 }
 
 #[derive(Debug)]
 struct Region {
-    width: u8,
-    height: u8,
-    quantity_to_fit_per_shape: [usize; NUM_MACHINES],
+    width: usize,
+    height: usize,
+    quantity_to_fit_per_shape: Vec<usize>,
 }
 
-impl From<&str> for Region {
-    fn from(string: &str) -> Region {
-        let (sizing, numbers) = string.split_once(":").unwrap();
-        let (width, height) = sizing.split_once("x").unwrap();
+fn parse_dimension(value: &str, line: &str) -> Result<usize, ParseError> {
+    value.trim().parse().map_err(|e: std::num::ParseIntError| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => ParseError::DimensionTooLarge(line.to_string()),
+        _ => ParseError::BadRegion(line.to_string()),
+    })
+}
 
-        let mut counts = [0usize; NUM_MACHINES];
-        let numbers = numbers
-            .split_whitespace()
-            .map(|n| n.parse().unwrap())
-            .take(NUM_MACHINES)
-            .enumerate();
-        for (i, n) in numbers {
-            counts[i] = n;
+impl TryFrom<&str> for Region {
+    type Error = ParseError;
+
+    fn try_from(string: &str) -> Result<Region, ParseError> {
+        let bad = || ParseError::BadRegion(string.to_string());
+        let (sizing, numbers) = string.split_once(":").ok_or_else(bad)?;
+        let (width, height) = sizing.split_once("x").ok_or_else(bad)?;
+        let width = parse_dimension(width, string)?;
+        let height = parse_dimension(height, string)?;
+
+        // The packing search needs a cell per square so the area has to fit too.
+        if width.checked_mul(height).is_none() {
+            return Err(ParseError::DimensionTooLarge(string.to_string()));
         }
 
-        Region {
-            width: width.parse().unwrap(),
-            height: height.parse().unwrap(),
+        // However many numbers there are is however many shapes this region wants.
+        let counts = numbers
+            .split_whitespace()
+            .map(|n| n.parse().map_err(|_| bad()))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Region {
+            width,
+            height,
             quantity_to_fit_per_shape: counts,
-        }
+        })
     }
 }
 
 impl Region {
     fn can_fit(&self, shapes: &[Shape]) -> bool {
-        self.can_fit_n(shapes) != 0
+        self.pack(shapes).is_some()
     }
 
+    // can_fit_n so that we can more easily test the example cases, it's how
+    // many presents got put down, or 0 if they can't all go in. A region that
+    // wants no presents fits with 0 too, so can_fit doesn't go by this.
+    #[cfg(test)]
     fn can_fit_n(&self, shapes: &[Shape]) -> usize {
-        // can_fit_n so that we can more easily test the example cases
+        self.pack(shapes).map_or(0, |packing| packing.placements.len())
+    }
+
+    fn pack(&self, shapes: &[Shape]) -> Option<Packing> {
         let shapes_to_fit = self.shapes_for_region(shapes);
         let total_shape_area = shapes_to_fit.iter().fold(0, |a, s| a + s.area());
-        if total_shape_area > self.width as usize * self.height as usize {
-            return 0;
+        if total_shape_area > self.width * self.height {
+            return None;
+        }
+
+        // Two shapes that are the same piece turned around are interchangeable, so
+        // search them as one kind. Each kind is stood in for by its first shape and
+        // remembers which shape each of its pieces really is.
+        let mut kinds: Vec<(Shape, usize, Vec<usize>)> = Vec::new();
+        let mut counts: Vec<usize> = Vec::new();
+        for (i, (shape, &n)) in shapes.iter().zip(&self.quantity_to_fit_per_shape).enumerate() {
+            let canonical = shape.canonical();
+            let same_piece = |(k, _, _): &(Shape, usize, Vec<usize>)| (k.rows, k.cols, &k.bits) == (canonical.rows, canonical.cols, &canonical.bits);
+            let kind = match kinds.iter().position(same_piece) {
+                Some(kind) => kind,
+                None => {
+                    kinds.push((canonical, i, Vec::new()));
+                    counts.push(0);
+                    kinds.len() - 1
+                }
+            };
+            kinds[kind].2.extend(std::iter::repeat_n(i, n));
+            counts[kind] += n;
         }
-        1
+
+        let orientations: Vec<Vec<Orientation>> = kinds.iter().map(|&(_, first, _)| shapes[first].orientations()).collect();
+        let mut packing = packing::pack(self.width, self.height, &orientations, &counts)?;
+        // Hand the kind's pieces back out as the shapes that were asked for, and
+        // find the same cells in that shape's own list of orientations.
+        let mut handed_out = vec![0; kinds.len()];
+        for placement in packing.placements.iter_mut() {
+            let kind = placement.shape;
+            let shape = kinds[kind].2[handed_out[kind]];
+            handed_out[kind] += 1;
+            let cells = &orientations[kind][placement.orientation];
+            placement.orientation = shapes[shape].orientations().iter().position(|o| o == cells).unwrap();
+            placement.shape = shape;
+        }
+        Some(packing)
     }
 
     fn shapes_for_region(&self, shape_definitions: &[Shape]) -> Vec<Shape> {
@@ -268,63 +493,71 @@ mod test_regions {
 
     #[test]
     fn test_parse() {
-        let region: Region = "40x42: 38 37 45 42 54 41".into();
+        let region = Region::try_from("40x42: 38 37 45 42 54 41").unwrap();
         assert_eq!(region.width, 40);
         assert_eq!(region.height, 42);
         assert_eq!(region.quantity_to_fit_per_shape, [38, 37, 45, 42, 54, 41]);
 
-        let region: Region = "4x4: 38 37 45 42 54 41".into();
+        let region = Region::try_from("4x4: 38 37 45 42 54 41").unwrap();
         assert_eq!(region.width, 4);
         assert_eq!(region.height, 4);
         assert_eq!(region.quantity_to_fit_per_shape, [38, 37, 45, 42, 54, 41]);
     }
 
+    #[test]
+    fn test_parse_big_and_bad() {
+        let region = Region::try_from("300x2: 1 2 3 4 5 6 7").unwrap();
+        assert_eq!(region.width, 300);
+        assert_eq!(region.quantity_to_fit_per_shape.len(), 7);
+
+        assert!(matches!(
+            Region::try_from("99999999999999999999x2: 1"),
+            Err(ParseError::DimensionTooLarge(_))
+        ));
+        assert!(matches!(
+            Region::try_from("4294967296x4294967296: 1"),
+            Err(ParseError::DimensionTooLarge(_))
+        ));
+        assert!(matches!(Region::try_from("4x: 1"), Err(ParseError::BadRegion(_))));
+    }
+
+    #[test]
+    fn test_p1_counts_shapes_from_input() {
+        let input = "0:\n####\n\n1:\n#\n\n2:\n##\n#.\n\n3x3: 0 1 2\n2x2: 1 0 0\n5x1: 1 1 0\n";
+        assert_eq!(p1(input), Ok(2));
+
+        let input = "0:\n#\n\n1x1: 1 1\n";
+        assert!(matches!(p1(input), Err(ParseError::WrongShapeCount(_))));
+    }
+
     fn example_regions() -> [Region; 3] {
         [
             Region {
                 width: 4,
                 height: 4,
-                quantity_to_fit_per_shape: [0, 0, 0, 0, 2, 0],
+                quantity_to_fit_per_shape: vec![0, 0, 0, 0, 2, 0],
             },
             Region {
                 width: 12,
                 height: 5,
-                quantity_to_fit_per_shape: [1, 0, 1, 0, 2, 2],
+                quantity_to_fit_per_shape: vec![1, 0, 1, 0, 2, 2],
             },
             Region {
                 width: 12,
                 height: 5,
-                quantity_to_fit_per_shape: [1, 0, 1, 0, 3, 2],
+                quantity_to_fit_per_shape: vec![1, 0, 1, 0, 3, 2],
             },
         ]
     }
 
     fn example_shapes() -> [Shape; 6] {
         [
-            Shape {
-                index: 0,
-                shape: [[1, 1, 1], [1, 1, 0], [1, 1, 0]],
-            },
-            Shape {
-                index: 1,
-                shape: [[1, 1, 1], [1, 1, 0], [0, 1, 1]],
-            },
-            Shape {
-                index: 2,
-                shape: [[0, 1, 1], [1, 1, 1], [1, 1, 0]],
-            },
-            Shape {
-                index: 3,
-                shape: [[1, 1, 0], [1, 1, 1], [1, 1, 0]],
-            },
-            Shape {
-                index: 4,
-                shape: [[1, 1, 1], [1, 0, 0], [1, 1, 1]],
-            },
-            Shape {
-                index: 5,
-                shape: [[1, 1, 1], [0, 1, 0], [1, 1, 1]],
-            },
+            Shape::from_grid(0, &[[1, 1, 1], [1, 1, 0], [1, 1, 0]]),
+            Shape::from_grid(1, &[[1, 1, 1], [1, 1, 0], [0, 1, 1]]),
+            Shape::from_grid(2, &[[0, 1, 1], [1, 1, 1], [1, 1, 0]]),
+            Shape::from_grid(3, &[[1, 1, 0], [1, 1, 1], [1, 1, 0]]),
+            Shape::from_grid(4, &[[1, 1, 1], [1, 0, 0], [1, 1, 1]]),
+            Shape::from_grid(5, &[[1, 1, 1], [0, 1, 0], [1, 1, 1]]),
         ]
     }
 
@@ -339,7 +572,56 @@ mod test_regions {
     fn test_region_2_example() {
         let region = &example_regions()[1];
         let shapes = example_shapes();
-        assert_eq!(region.can_fit_n(&shapes[..]), 5);
+        // 12x5: 1 0 1 0 2 2 is six presents, and the puzzle says they all fit.
+        assert_eq!(region.can_fit_n(&shapes[..]), 6);
+    }
+
+    #[test]
+    fn test_no_presents_fits() {
+        let region = Region { width: 3, height: 3, quantity_to_fit_per_shape: vec![0; 6] };
+        let shapes = example_shapes();
+        assert!(region.can_fit(&shapes[..]));
+        assert_eq!(region.can_fit_n(&shapes[..]), 0);
+    }
+
+    #[test]
+    fn test_region_2_layout() {
+        let region = &example_regions()[1];
+        let shapes = example_shapes();
+        let packing = region.pack(&shapes[..]).unwrap();
+        let drawn = packing.to_string();
+
+        assert_eq!(drawn.lines().count(), 5);
+        assert!(drawn.lines().all(|line| line.len() == 12));
+        // Every present shows up with exactly its own area.
+        for (i, placement) in packing.placements.iter().enumerate() {
+            let label = (b'A' + i as u8) as char;
+            let area = shapes[placement.shape].area();
+            assert_eq!(drawn.chars().filter(|&c| c == label).count(), area);
+        }
+    }
+
+    #[test]
+    fn test_same_piece_twice() {
+        // Shape 1 is just shape 0 flipped, they're searched as one kind but the
+        // layout still says which is which.
+        let shapes = [
+            Shape::from_grid(0, &[[1, 0], [1, 0], [1, 1]]),
+            Shape::from_grid(1, &[[0, 1], [0, 1], [1, 1]]),
+        ];
+        let region = Region { width: 4, height: 6, quantity_to_fit_per_shape: vec![2, 2] };
+        let packing = region.pack(&shapes).unwrap();
+        assert_eq!(packing.placements.len(), 4);
+        let mut placed: Vec<usize> = packing.placements.iter().map(|p| p.shape).collect();
+        placed.sort();
+        assert_eq!(placed, vec![0, 0, 1, 1]);
+        for placement in &packing.placements {
+            let cells = &shapes[placement.shape].orientations()[placement.orientation];
+            for &(dr, dc) in cells {
+                let (r, c) = ((placement.row as isize + dr) as usize, (placement.col as isize + dc) as usize);
+                assert_eq!(packing.cells[r][c].map(|i| packing.placements[i]), Some(*placement));
+            }
+        }
     }
 
     #[test]
@@ -350,8 +632,6 @@ mod test_regions {
     }
 }
 
-// TODO: define rotate/flip functions, overlaps?
-
 fn p2(_raw_data: &str) -> ResultType {
     0
 }
@@ -0,0 +1,218 @@
+use std::fmt;
+
+/* The actual packing search. Shapes come in as every distinct orientation of
+ * their cells, already normalized so the top left most filled cell is (0, 0)
+ * and everything else is an offset from it:
+ *
+ *   .##      (0,0) (0,1)
+ *   ##.  ->  (1,-1) (1,0)
+ *
+ * The search walks the region one cell at a time in reading order. The first
+ * cell nobody has decided on yet either becomes the anchor of some piece that
+ * is still left to place, or gets left empty. Left empty is only allowed while
+ * there's more free space than area still to place, which prunes a LOT. Since
+ * we pick by shape kind and not by individual piece, two copies of the same
+ * present are never tried in swapped order either.
+ */
+pub type Orientation = Vec<(isize, isize)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Placement {
+    pub shape: usize,
+    pub orientation: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Packing {
+    pub placements: Vec<Placement>,
+    /// `cells[row][col]` is the index into `placements` covering that cell.
+    pub cells: Vec<Vec<Option<usize>>>,
+}
+
+impl fmt::Display for Packing {
+    // Every piece gets its own letter, cycling through A-Z then a-z.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const LABELS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+        for row in &self.cells {
+            for cell in row {
+                let ch = match cell {
+                    Some(piece) => LABELS[piece % LABELS.len()] as char,
+                    None => '.',
+                };
+                write!(f, "{}", ch)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+struct Search<'a> {
+    width: usize,
+    height: usize,
+    orientations: &'a [Vec<Orientation>],
+    remaining: Vec<usize>,
+    // Bitboard, one bit per cell in reading order. Skipped cells get set too.
+    filled: Vec<u64>,
+    cells: usize,
+    placements: Vec<Placement>,
+}
+
+impl Search<'_> {
+    fn is_filled(&self, idx: usize) -> bool {
+        self.filled[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    fn toggle(&mut self, idx: usize) {
+        self.filled[idx / 64] ^= 1 << (idx % 64);
+    }
+
+    fn fits(&self, cell: usize, orientation: &Orientation) -> bool {
+        let (row, col) = ((cell / self.width) as isize, (cell % self.width) as isize);
+        orientation.iter().all(|&(dr, dc)| {
+            let (r, c) = (row + dr, col + dc);
+            r >= 0
+                && c >= 0
+                && (r as usize) < self.height
+                && (c as usize) < self.width
+                && !self.is_filled(r as usize * self.width + c as usize)
+        })
+    }
+
+    // Placing and lifting a piece are the same XOR since fits() already checked the spot.
+    fn toggle_piece(&mut self, cell: usize, shape: usize, orientation: usize) {
+        let (row, col) = ((cell / self.width) as isize, (cell % self.width) as isize);
+        for &(dr, dc) in &self.orientations[shape][orientation] {
+            self.toggle((row + dr) as usize * self.width + (col + dc) as usize);
+        }
+    }
+
+    fn search(&mut self, mut cell: usize, slack: usize) -> bool {
+        if self.remaining.iter().all(|&n| n == 0) {
+            return true;
+        }
+        while cell < self.cells && self.is_filled(cell) {
+            cell += 1;
+        }
+        if cell == self.cells {
+            return false;
+        }
+
+        for shape in 0..self.orientations.len() {
+            if self.remaining[shape] == 0 {
+                continue;
+            }
+            for o in 0..self.orientations[shape].len() {
+                if !self.fits(cell, &self.orientations[shape][o]) {
+                    continue;
+                }
+
+                self.toggle_piece(cell, shape, o);
+                self.remaining[shape] -= 1;
+                self.placements.push(Placement {
+                    shape,
+                    orientation: o,
+                    row: cell / self.width,
+                    col: cell % self.width,
+                });
+
+                if self.search(cell + 1, slack) {
+                    return true;
+                }
+
+                self.placements.pop();
+                self.remaining[shape] += 1;
+                self.toggle_piece(cell, shape, o);
+            }
+        }
+
+        // Nothing anchored here worked, so try leaving the cell empty if we can afford it.
+        if slack > 0 {
+            self.toggle(cell);
+            let found = self.search(cell + 1, slack - 1);
+            self.toggle(cell);
+            return found;
+        }
+        false
+    }
+}
+
+/// Try to place `counts[i]` copies of shape `i` (in any of its `orientations[i]`)
+/// into a `width` x `height` region without overlaps.
+pub fn pack(width: usize, height: usize, orientations: &[Vec<Orientation>], counts: &[usize]) -> Option<Packing> {
+    assert_eq!(orientations.len(), counts.len());
+    let areas: Vec<usize> = orientations
+        .iter()
+        .map(|o| o.first().map_or(0, |cells| cells.len()))
+        .collect();
+    // An empty shape fits anywhere, so don't bother searching for spots for it.
+    let counts: Vec<usize> = counts.iter().zip(&areas).map(|(&n, &a)| if a == 0 { 0 } else { n }).collect();
+    let needed: usize = areas.iter().zip(&counts).map(|(a, n)| a * n).sum();
+    let available = width * height;
+    if needed > available {
+        return None;
+    }
+
+    // Easy case first: if every piece gets a box of its own there's nothing to search.
+    if let Some(packing) = tile(width, height, orientations, &counts) {
+        return Some(packing);
+    }
+
+    let mut search = Search {
+        width,
+        height,
+        orientations,
+        remaining: counts,
+        filled: vec![0; available.div_ceil(64)],
+        cells: available,
+        placements: Vec::new(),
+    };
+
+    if !search.search(0, available - needed) {
+        return None;
+    }
+
+    Some(layout(width, height, orientations, search.placements))
+}
+
+/* Lay the pieces out on a grid of equal boxes, each box big enough for any of
+ * the shapes in their first orientation. This is how the real input fits.
+ */
+fn tile(width: usize, height: usize, orientations: &[Vec<Orientation>], counts: &[usize]) -> Option<Packing> {
+    let used = || (0..orientations.len()).filter(|&s| counts[s] > 0);
+    let left = |s: usize| orientations[s][0].iter().map(|&(_, dc)| dc).min().unwrap_or(0);
+    let box_height = used().map(|s| orientations[s][0].iter().map(|&(dr, _)| dr + 1).max().unwrap_or(0)).max()?;
+    let box_width = used().map(|s| orientations[s][0].iter().map(|&(_, dc)| dc + 1).max().unwrap_or(0) - left(s)).max()?;
+
+    let per_row = width / box_width as usize;
+    let boxes = per_row * (height / box_height as usize);
+    if counts.iter().sum::<usize>() > boxes {
+        return None;
+    }
+
+    let mut placements = Vec::new();
+    for s in used() {
+        for _ in 0..counts[s] {
+            let n = placements.len();
+            placements.push(Placement {
+                shape: s,
+                orientation: 0,
+                row: (n / per_row) * box_height as usize,
+                col: (n % per_row) * box_width as usize + (-left(s)) as usize,
+            });
+        }
+    }
+    Some(layout(width, height, orientations, placements))
+}
+
+fn layout(width: usize, height: usize, orientations: &[Vec<Orientation>], placements: Vec<Placement>) -> Packing {
+    let mut cells = vec![vec![None; width]; height];
+    for (i, p) in placements.iter().enumerate() {
+        for &(dr, dc) in &orientations[p.shape][p.orientation] {
+            cells[(p.row as isize + dr) as usize][(p.col as isize + dc) as usize] = Some(i);
+        }
+    }
+    Packing { placements, cells }
+}